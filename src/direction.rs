@@ -1,4 +1,4 @@
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub enum Direction4 {
 	North,
 	East,
@@ -25,9 +25,141 @@ impl Direction4 {
 			Self::West => 3,
 		}
 	}
+
+	/// Gets the direction a quarter turn clockwise from this one.
+	pub fn get_clockwise(self) -> Self {
+		match self {
+			Self::North => Self::East,
+			Self::East => Self::South,
+			Self::South => Self::West,
+			Self::West => Self::North,
+		}
+	}
+
+	/// Gets the axis that this direction lies along.
+	pub fn get_axis(self) -> Direction2 {
+		match self {
+			Self::North | Self::South => Direction2::NorthSouth,
+			Self::East | Self::West => Direction2::EastWest,
+		}
+	}
+
+	/// Gets the position one tile in this direction, None if that is outside of a world of the given size.
+	pub fn offset_pos(self, pos: [u16; 2], size: [u16; 2]) -> Option<[u16; 2]> {
+		let out = match self {
+			Self::North => [pos[0], pos[1].checked_sub(1)?],
+			Self::East => [pos[0].checked_add(1)?, pos[1]],
+			Self::South => [pos[0], pos[1].checked_add(1)?],
+			Self::West => [pos[0].checked_sub(1)?, pos[1]],
+		};
+		match out[0] < size[0] && out[1] < size[1] {
+			true => Some(out),
+			false => None,
+		}
+	}
 }
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum Direction2 {
 	NorthSouth,
 	EastWest,
-}
\ No newline at end of file
+}
+
+/// A direction to one of the eight tiles around a tile, including diagonals.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Direction8 {
+	North,
+	NorthEast,
+	East,
+	SouthEast,
+	South,
+	SouthWest,
+	West,
+	NorthWest,
+}
+
+impl Direction8 {
+	pub fn new(int_val: u8) -> Self {
+		match int_val {
+			0 => Self::North,
+			1 => Self::NorthEast,
+			2 => Self::East,
+			3 => Self::SouthEast,
+			4 => Self::South,
+			5 => Self::SouthWest,
+			6 => Self::West,
+			7 => Self::NorthWest,
+			_ => panic!(),
+		}
+	}
+
+	pub fn get_int_val(self) -> u8 {
+		match self {
+			Self::North => 0,
+			Self::NorthEast => 1,
+			Self::East => 2,
+			Self::SouthEast => 3,
+			Self::South => 4,
+			Self::SouthWest => 5,
+			Self::West => 6,
+			Self::NorthWest => 7,
+		}
+	}
+
+	pub fn get_opposite(self) -> Self {
+		Self::new((self.get_int_val() + 4) % 8)
+	}
+
+	pub fn is_diagonal(self) -> bool {
+		self.get_int_val() % 2 == 1
+	}
+
+	/// Gets the same direction as a Direction4, None for diagonals.
+	pub fn get_direction4(self) -> Option<Direction4> {
+		match self.is_diagonal() {
+			true => None,
+			false => Some(Direction4::new(self.get_int_val() / 2)),
+		}
+	}
+
+	/// Gets the axis that this direction lies along, None for diagonals.
+	pub fn get_axis(self) -> Option<Direction2> {
+		Some(self.get_direction4()?.get_axis())
+	}
+
+	/// Gets how far one step in this direction moves along each axis.
+	pub fn get_offset(self) -> [i32; 2] {
+		match self {
+			Self::North => [0, -1],
+			Self::NorthEast => [1, -1],
+			Self::East => [1, 0],
+			Self::SouthEast => [1, 1],
+			Self::South => [0, 1],
+			Self::SouthWest => [-1, 1],
+			Self::West => [-1, 0],
+			Self::NorthWest => [-1, -1],
+		}
+	}
+
+	/// Gets the direction from one tile to a tile next to it, None if they are not next to each other.
+	pub fn from_neighbors(from: [u16; 2], to: [u16; 2]) -> Option<Self> {
+		let offset = [to[0] as i32 - from[0] as i32, to[1] as i32 - from[1] as i32];
+		(0..8).map(Self::new).find(|direction| direction.get_offset() == offset)
+	}
+
+	/// Gets the position one tile in this direction, None if that is outside of a world of the given size.
+	pub fn offset_pos(self, pos: [u16; 2], size: [u16; 2]) -> Option<[u16; 2]> {
+		let [offset_x, offset_y] = self.get_offset();
+		let out = [pos[0].checked_add_signed(offset_x as i16)?, pos[1].checked_add_signed(offset_y as i16)?];
+		match out[0] < size[0] && out[1] < size[1] {
+			true => Some(out),
+			false => None,
+		}
+	}
+}
+
+impl From<Direction4> for Direction8 {
+	fn from(direction: Direction4) -> Self {
+		Self::new(direction.get_int_val() * 2)
+	}
+}
@@ -0,0 +1,80 @@
+// Money, what interactions cost and the upkeep of what has been built
+
+use std::fmt;
+
+use super::texture;
+use super::tile;
+use super::vertex;
+use super::world;
+
+/// Money that new worlds start with.
+pub const STARTING_MONEY: i64 = 10_000_000;
+// Width of a digit sprite in tiles, digits are drawn closer together than whole tiles
+const DIGIT_WIDTH: f32 = 7. / 16.;
+
+/// An interaction that was refused because it costs more than the money left.
+pub struct CannotAfford {
+	pub cost: i64,
+	pub money: i64,
+}
+
+impl fmt::Display for CannotAfford {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Cannot afford ${}, only ${} left", self.cost, self.money)
+	}
+}
+
+/// Gets what it costs to change a tile from one state to another.
+fn get_tile_cost(before: &tile::Tile, after: &tile::Tile) -> i64 {
+	let mut cost = 0;
+	if before.ground != after.ground {
+		cost += after.ground.get_build_cost();
+	}
+	if before.cover != after.cover {
+		cost += match (before.cover.get_road_quarters(), after.cover.get_road_quarters()) {
+			// Changing a road is paid for per quarter, upgrades cost the difference and anything else costs removing the old quarter
+			(Some(before_quarters), Some(after_quarters)) => before_quarters.iter().zip(after_quarters.iter())
+				.filter(|(before_quarter, after_quarter)| before_quarter != after_quarter)
+				.map(|(before_quarter, after_quarter)| match after_quarter.get_build_cost() > before_quarter.get_build_cost() {
+					true => after_quarter.get_build_cost() - before_quarter.get_build_cost(),
+					false => before_quarter.get_demolish_cost(),
+				})
+				.sum(),
+			_ => before.cover.get_demolish_cost() + after.cover.get_build_cost(),
+		};
+	}
+	cost
+}
+
+/// Gets the total cost of changing the tiles from their states before to what they are now.
+pub fn get_cost(tiles_before: &[([u16; 2], tile::Tile)], tiles: &ndarray::Array2<tile::Tile>) -> i64 {
+	tiles_before.iter()
+		.map(|(pos, tile_before)| (tile_before, &tiles[[pos[0] as usize, pos[1] as usize]]))
+		.filter(|(tile_before, tile_after)| tile_before != tile_after)
+		.map(|(tile_before, tile_after)| get_tile_cost(tile_before, tile_after))
+		.sum()
+}
+
+/// Charges the upkeep of everything built and collects taxes from buildings.
+pub fn tick(world: &mut world::World) {
+	if world.is_sandbox {
+		return;
+	}
+	let upkeep: i64 = world.tiles.iter().map(|tile| tile.cover.get_upkeep()).sum();
+	world.money -= upkeep;
+}
+
+/// Draws an amount of money starting at a position measured in tiles, with a red sign if it is more than can be afforded.
+pub fn render_money(amount: i64, pos: [f32; 2], can_afford: bool) -> Vec<vertex::Vertex> {
+	let mut ids = vec![match can_afford {
+		true => texture::Texture::MoneySign,
+		false => texture::Texture::MoneySignRed,
+	}.get_texture_id()];
+	if amount < 0 {
+		ids.push(texture::Texture::Minus.get_texture_id());
+	}
+	ids.extend(amount.unsigned_abs().to_string().bytes().map(|digit| texture::Texture::Digit0.get_texture_id() + digit - b'0'));
+	ids.into_iter().enumerate()
+		.flat_map(|(index, id)| texture::generate_tris_from_id_at(id, [pos[0] + index as f32 * DIGIT_WIDTH, pos[1]]))
+		.collect()
+}
@@ -0,0 +1,64 @@
+// Renders worlds to images on the CPU so no window or GPU is needed
+
+use std::fmt;
+
+use super::texture;
+use super::world;
+
+// Most tiles in a full export, which keeps its image to 1 GiB
+const MAX_FULL_TILES: u64 = 1024 * 1024;
+
+/// How a world is drawn into an image.
+#[derive(Copy, Clone)]
+pub enum ExportMode {
+	/// One pixel per tile colored by its ground and cover.
+	Minimap,
+	/// Every tile drawn with its sprites.
+	Full,
+}
+
+/// A full export that was refused because its image would take too much memory.
+pub struct TooLarge {
+	pub width: u16,
+	pub height: u16,
+}
+
+impl fmt::Display for TooLarge {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "world is {}x{}, full exports can have at most {MAX_FULL_TILES} tiles", self.width, self.height)
+	}
+}
+
+pub fn export(world: &world::World, mode: ExportMode) -> Result<image::RgbaImage, TooLarge> {
+	match mode {
+		ExportMode::Minimap => Ok(export_minimap(world)),
+		ExportMode::Full => {
+			let (width, height) = (world.get_width(), world.get_height());
+			if width as u64 * height as u64 > MAX_FULL_TILES {
+				return Err(TooLarge { width, height });
+			}
+			Ok(export_full(world, &texture::load_atlas()))
+		}
+	}
+}
+
+fn export_minimap(world: &world::World) -> image::RgbaImage {
+	image::RgbaImage::from_fn(world.get_width() as u32, world.get_height() as u32, |x, y| {
+		let [red, green, blue] = world.tiles[[x as usize, y as usize]].get_map_color();
+		image::Rgba([red, green, blue, 0xFF])
+	})
+}
+
+fn export_full(world: &world::World, atlas: &image::RgbaImage) -> image::RgbaImage {
+	let sprite_size = texture::SPRITE_SIZE;
+	let mut out = image::RgbaImage::new(world.get_width() as u32 * sprite_size, world.get_height() as u32 * sprite_size);
+	for ((x, y), tile) in world.tiles.indexed_iter() {
+		let corner_texture_ids = world.road_network.get_corner_texture_ids().get(&[x as u16, y as u16]).cloned().unwrap_or_default();
+		for id in tile.get_texture_ids().into_iter().chain(corner_texture_ids) {
+			let [atlas_x, atlas_y] = texture::get_atlas_pos(id);
+			let sprite = image::imageops::crop_imm(atlas, atlas_x, atlas_y, sprite_size, sprite_size);
+			image::imageops::overlay(&mut out, &*sprite, x as i64 * sprite_size as i64, y as i64 * sprite_size as i64);
+		}
+	}
+	out
+}
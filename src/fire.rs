@@ -0,0 +1,120 @@
+// Fire spreading between trees and buildings and burning them down
+
+use super::direction;
+use super::random;
+use super::tile;
+use super::world;
+use super::world_event;
+
+const IGNITION_SALT: u64 = 0x300;
+const SPREAD_SALT: u64 = 0x301;
+
+/// Rules of the fire simulation.
+#[derive(Copy, Clone)]
+pub struct FireParameters {
+	/// Chance per tick that a tree or building catches fire by itself.
+	pub ignition_chance: f32,
+	/// Chance per tick that fire spreads to each neighboring tree or building.
+	pub spread_chance: f32,
+	/// Multiplier for the spread chance onto tiles that are on or next to water or swamp.
+	pub wet_spread_multiplier: f32,
+	/// Ticks a tree or building burns for before it is burnt down.
+	pub burn_ticks: u8,
+	/// Ticks that a tile with nothing left to burn, such as after a bomb, stays on fire.
+	pub ember_ticks: u8,
+}
+
+impl Default for FireParameters {
+	fn default() -> Self {
+		Self { ignition_chance: 0.000_000_01, spread_chance: 0.05, wet_spread_multiplier: 0.2, burn_ticks: 60, ember_ticks: 20 }
+	}
+}
+
+/// Gets if a tile is on or next to water or swamp ground.
+fn is_wet(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], size: [u16; 2]) -> bool {
+	tiles[[pos[0] as usize, pos[1] as usize]].ground.is_water() || (0..4)
+		.filter_map(|direction| direction::Direction4::new(direction).offset_pos(pos, size))
+		.any(|[x, y]| tiles[[x as usize, y as usize]].ground.is_water())
+}
+
+/// Leaves burnt ground or rubble behind after a tile has finished burning, only the cover that could burn is removed.
+fn burn_out(tile: &mut tile::Tile, pos: [u16; 2], events: &mut Vec<world_event::WorldEvent>, changed_positions: &mut Vec<[u16; 2]>) {
+	tile.fire = 0;
+	let burnt_cover = tile.cover;
+	match burnt_cover {
+		tile::Cover::Building(..) => tile.cover = tile::Cover::Rubble,
+		tile::Cover::Tree(_) => tile.cover = tile::Cover::None,
+		_ => {},
+	}
+	if tile.cover != burnt_cover {
+		changed_positions.push(pos);
+	}
+	if tile.ground.is_land() && tile.cover.can_go_on_ground(tile::Ground::Ash) {
+		tile.ground = tile::Ground::Ash;
+	}
+	if burnt_cover.is_flammable() {
+		events.push(world_event::WorldEvent::CoverBurnt { pos, cover: burnt_cover });
+	}
+}
+
+/// Advances the fires by one tick, adding the positions whose cover changed to changed_positions.
+pub fn tick(world: &mut world::World, changed_positions: &mut Vec<[u16; 2]>) -> Vec<world_event::WorldEvent> {
+	let parameters = world.fire_parameters;
+	let seed = world.seed;
+	let tick_count = world.tick_count;
+	let size = [world.get_width(), world.get_height()];
+	let mut events = Vec::new();
+
+	// Find what catches fire before anything burns so that fire only spreads one tile per tick
+	let mut ignitions = Vec::new();
+	for ((x, y), tile) in world.tiles.indexed_iter() {
+		let pos = [x as u16, y as u16];
+
+		// Catch fire by itself
+		if tile.fire == 0 {
+			if tile.cover.is_flammable() && random::hash_chance(seed, &[tick_count, IGNITION_SALT, x as u64, y as u64], parameters.ignition_chance) {
+				ignitions.push(pos);
+			}
+			continue;
+		}
+
+		// Spread to neighbors
+		for direction in (0..4).map(direction::Direction4::new) {
+			let neighbor_pos = match direction.offset_pos(pos, size) {
+				Some(valid) => valid,
+				None => continue,
+			};
+			let neighbor = &world.tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]];
+			if neighbor.fire > 0 || !neighbor.cover.is_flammable() {
+				continue;
+			}
+			let chance = match is_wet(&world.tiles, neighbor_pos, size) {
+				true => parameters.spread_chance * parameters.wet_spread_multiplier,
+				false => parameters.spread_chance,
+			};
+			if random::hash_chance(seed, &[tick_count, SPREAD_SALT, x as u64, y as u64, direction.get_int_val() as u64], chance) {
+				ignitions.push(neighbor_pos);
+			}
+		}
+	}
+
+	// Burn
+	for ((x, y), tile) in world.tiles.indexed_iter_mut() {
+		if tile.fire == 0 {
+			continue;
+		}
+		let burn_ticks = match tile.cover.is_flammable() {
+			true => parameters.burn_ticks,
+			false => parameters.ember_ticks,
+		};
+		match tile.fire >= burn_ticks {
+			true => burn_out(tile, [x as u16, y as u16], &mut events, changed_positions),
+			false => tile.fire += 1,
+		}
+	}
+
+	for [x, y] in ignitions {
+		world.tiles[[x as usize, y as usize]].fire = 1;
+	}
+	events
+}
@@ -0,0 +1,92 @@
+// Trees growing through their stages and spreading over fertile land
+
+use super::direction;
+use super::random;
+use super::tile;
+use super::world;
+
+// Salts so that each random event is independent
+const GROWTH_SALT: u64 = 0x100;
+const SPREAD_SALT: u64 = 0x101;
+const SPREAD_DIRECTION_SALT: u64 = 0x102;
+const LITTER_SALT: u64 = 0x103;
+const LITTER_POS_SALT: u64 = 0x104;
+
+/// Rates of the forest simulation, each is a chance per tree per tick.
+#[derive(Copy, Clone)]
+pub struct ForestParameters {
+	/// Chance that a tree grows into its next stage.
+	pub growth_chance: f32,
+	/// Chance that a mature or old tree drops a sapling on a neighboring tile.
+	pub spread_chance: f32,
+	/// Chance that an old tree turns the ground under or next to it into leaf litter.
+	pub litter_chance: f32,
+}
+
+impl Default for ForestParameters {
+	fn default() -> Self {
+		Self { growth_chance: 0.002, spread_chance: 0.001, litter_chance: 0.0005 }
+	}
+}
+
+/// Advances the trees by one tick, adding the positions whose cover changed to changed_positions.
+pub fn tick(world: &mut world::World, changed_positions: &mut Vec<[u16; 2]>) {
+	let parameters = world.forest_parameters;
+	let seed = world.seed;
+	let tick_count = world.tick_count;
+	let size = [world.get_width(), world.get_height()];
+	let random = |salt: u64, pos: [u16; 2]| random::hash(seed, &[tick_count, salt, pos[0] as u64, pos[1] as u64]);
+	let random_unit = |salt: u64, pos: [u16; 2]| random::hash_unit(seed, &[tick_count, salt, pos[0] as u64, pos[1] as u64]);
+
+	// Saplings and leaves are put down after every tree has had its turn so that new saplings do not grow or spread in the same tick
+	let mut saplings = Vec::new();
+	let mut litter = Vec::new();
+	for (x, y) in ndarray::indices(world.tiles.dim()) {
+		let stage = match world.tiles[[x, y]].cover {
+			tile::Cover::Tree(stage) => stage,
+			_ => continue,
+		};
+		let pos = [x as u16, y as u16];
+
+		// Grow
+		if let Some(next_stage) = stage.get_next() {
+			if random_unit(GROWTH_SALT, pos) < parameters.growth_chance {
+				world.tiles[[x, y]].cover = tile::Cover::Tree(next_stage);
+				changed_positions.push(pos);
+			}
+		}
+
+		// Spread onto an empty fertile neighbor, anything else on the neighbor blocks spreading
+		if stage != tile::TreeStage::Sapling && random_unit(SPREAD_SALT, pos) < parameters.spread_chance {
+			let direction = direction::Direction4::new((random(SPREAD_DIRECTION_SALT, pos) % 4) as u8);
+			if let Some(target_pos) = direction.offset_pos(pos, size) {
+				saplings.push(target_pos);
+			}
+		}
+
+		// Drop leaves on the tile or a neighbor
+		if stage == tile::TreeStage::Old && random_unit(LITTER_SALT, pos) < parameters.litter_chance {
+			let target_pos = match random(LITTER_POS_SALT, pos) % 5 {
+				4 => Some(pos),
+				direction => direction::Direction4::new(direction as u8).offset_pos(pos, size),
+			};
+			litter.extend(target_pos);
+		}
+	}
+
+	let sapling = tile::Cover::Tree(tile::TreeStage::Sapling);
+	for [x, y] in saplings {
+		let target = &mut world.tiles[[x as usize, y as usize]];
+		if matches!(target.cover, tile::Cover::None) && sapling.can_go_on_ground(target.ground) {
+			target.cover = sapling;
+			changed_positions.push([x, y]);
+		}
+	}
+	let ground = tile::Ground::LeafLitter;
+	for [x, y] in litter {
+		let target = &mut world.tiles[[x as usize, y as usize]];
+		if target.ground.is_land() && target.ground.is_fertile() && target.cover.can_go_on_ground(ground) {
+			target.ground = ground;
+		}
+	}
+}
@@ -3,6 +3,8 @@ use crate::mouse;
 use super::vertex;
 use super::texture;
 use super::tile;
+use super::direction;
+use super::economy;
 use counted_array::counted_array;
 
 #[derive(Copy, Clone)]
@@ -16,26 +18,42 @@ counted_array!(const TOOLS: [Tool; _] = [
 	Tool { icon: texture::Texture::Water, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Water)) },
 	Tool { icon: texture::Texture::Bricks, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Bricks)) },
 	Tool { icon: texture::Texture::Bomb, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::DemolishCover) },
-	Tool { icon: texture::Texture::Tree, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::BuildCover(tile::Cover::Tree)) },
-	Tool { icon: texture::Texture::TestBuilding, interaction: interaction::InteractionShape::Dot(interaction::TileInteraction::BuildCover(tile::Cover::TestBuilding)) },
+	Tool { icon: texture::Texture::Tree, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::BuildCover(tile::Cover::Tree(tile::TreeStage::Mature))) },
+	Tool { icon: texture::Texture::House, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Residential, direction::Direction4::South))) },
+	Tool { icon: texture::Texture::Shop, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Commercial, direction::Direction4::South))) },
+	Tool { icon: texture::Texture::Factory, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Industrial, direction::Direction4::South))) },
 	Tool { icon: texture::Texture::Gravel, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Gravel)) },
-	Tool { icon: texture::Texture::GravelRoadGUI, interaction: interaction::InteractionShape::RoadLine(tile::Road::Gravel) },
+	Tool { icon: texture::Texture::GravelRoadGUI, interaction: interaction::InteractionShape::RoadLine(tile::Road::Gravel, interaction::RoadDrag::Straight) },
+	Tool { icon: texture::Texture::DirtRoadGUI, interaction: interaction::InteractionShape::RoadLine(tile::Road::DirtPath, interaction::RoadDrag::Straight) },
+	Tool { icon: texture::Texture::PavedRoadGUI, interaction: interaction::InteractionShape::RoadLine(tile::Road::Paved, interaction::RoadDrag::Straight) },
+	Tool { icon: texture::Texture::HighwayGUI, interaction: interaction::InteractionShape::RoadLine(tile::Road::Highway, interaction::RoadDrag::Straight) },
+	Tool { icon: texture::Texture::ConnectRoadGUI, interaction: interaction::InteractionShape::Dot(interaction::TileInteraction::ConnectRoad(tile::Road::Gravel)) },
+	Tool { icon: texture::Texture::EraseRoadGUI, interaction: interaction::InteractionShape::EraseRoadLine },
+	Tool { icon: texture::Texture::RoadUpgrade, interaction: interaction::InteractionShape::ChangeRoadLine(interaction::RoadChange::Upgrade) },
+	Tool { icon: texture::Texture::RoadDowngrade, interaction: interaction::InteractionShape::ChangeRoadLine(interaction::RoadChange::Downgrade) },
 	Tool { icon: texture::Texture::LeafLitter, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::LeafLitter)) },
 	Tool { icon: texture::Texture::Swamp, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Swamp)) },
 	Tool { icon: texture::Texture::Sand, interaction: interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Sand)) },
+	Tool { icon: texture::Texture::TownHall, interaction: interaction::InteractionShape::Dot(interaction::TileInteraction::BuildStructure(tile::Structure::TownHall, direction::Direction4::South)) },
+	Tool { icon: texture::Texture::Warehouse, interaction: interaction::InteractionShape::Dot(interaction::TileInteraction::BuildStructure(tile::Structure::Warehouse, direction::Direction4::South)) },
+	Tool { icon: texture::Texture::Fire, interaction: interaction::InteractionShape::Dot(interaction::TileInteraction::Ignite) },
 ]);
 
-pub struct GUI {
+pub struct Gui {
 	pub is_open: bool
 }
 
-impl GUI {
+impl Gui {
 	pub fn new() -> Self {
 		Self { is_open: false }
 	}
 
-	pub fn render(&self) -> Vec<vertex::Vertex> {
+	/// Draws the tool menu if it is open and the money left, which is None in sandbox mode.
+	pub fn render(&self, money: Option<i64>) -> Vec<vertex::Vertex> {
 		let mut tris = Vec::new();
+		if let Some(money) = money {
+			tris.extend(economy::render_money(money, [0., 0.], money >= 0));
+		}
 		if self.is_open {
 			for (index, tool) in TOOLS.iter().enumerate() {
 				tris.extend(tool.icon.generate_tris([index as u16 % 8 + 4, index as u16 / 8 + 4]));
@@ -47,12 +65,9 @@ impl GUI {
 	pub fn click(&mut self, mouse: &mouse::Mouse, interaction: &mut interaction::InteractionShape) {
 		if mouse.gui_pos[0] > 3 && mouse.gui_pos[0] < 12 && mouse.gui_pos[1] > 3 && mouse.gui_pos[1] < 12 {
 			let index = mouse.gui_pos[0] - 4 + (mouse.gui_pos[1] - 4) * 8;
-			match TOOLS.get(index as usize) {
-				Some(valid) => {
-					*interaction = valid.interaction;
-					self.is_open = false;
-				},
-				None => {},
+			if let Some(valid) = TOOLS.get(index as usize) {
+				*interaction = valid.interaction;
+				self.is_open = false;
 			}
 		}
 	}
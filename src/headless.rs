@@ -0,0 +1,268 @@
+// Command line mode that edits worlds without opening a window
+
+use std::{path, slice};
+
+use super::direction;
+use super::export;
+use super::import;
+use super::interaction;
+use super::mouse;
+use super::pathfinding;
+use super::road_network;
+use super::zoning;
+use super::terrain;
+use super::tile;
+use super::world;
+
+pub const USAGE: &str = "Usage: grid_sim --headless [options]
+Options are applied in order:
+  --new WIDTH HEIGHT              Create a flat world
+  --generate SEED WIDTH HEIGHT    Generate a world from a seed
+  --load PATH                     Load a saved world
+  --import PATH                   Import a world from a palette-mapped PNG
+  --interact TOOL SHAPE X Y [X Y] Use a tool on the tiles from the first to the second position
+  --radius RADIUS                 Set the radius of later brush interactions
+  --rotate                        Turn the buildings of later interactions a quarter turn clockwise, corner roads go north or south first when odd
+  --ticks COUNT                   Run simulation ticks
+  --sandbox on|off                Turn sandbox mode where interactions are free on or off
+  --money                         Print the money left
+  --roads                         Print the road network graph
+  --check-roads                   Check that the road network matches one rebuilt from the tiles
+  --zones                         Print how many buildings each zone has and the demand for more
+  --vehicles                      Print where each vehicle is and where it is going
+  --path MODE X Y X Y             Print the cheapest path between two tiles, MODE is road, offroad or newroad
+  --save PATH                     Save the world
+  --export PATH [full]            Export the world as a PNG, one pixel per tile unless full is given
+Tools: grass, water, bricks, gravel, leaf_litter, swamp, sand, ash, tree, residential, commercial, industrial, town_hall, warehouse, bomb, fire, connect_road,
+  road, dirt_path, paved_road, highway, upgrade_road, downgrade_road, erase_road
+Shapes: dot, rectangle, brush, ellipse, ellipse_outline, line, corner, route
+  (brush paints around the last position, line is only for road tools, corner and route only for building roads)";
+
+fn next_arg<'a>(args: &mut slice::Iter<'a, String>, option: &str) -> Result<&'a str, String> {
+	match args.next() {
+		Some(valid) => Ok(valid),
+		None => Err(format!("missing value for {option}")),
+	}
+}
+
+fn next_number<T: std::str::FromStr>(args: &mut slice::Iter<String>, option: &str) -> Result<T, String> {
+	let arg = next_arg(args, option)?;
+	arg.parse().map_err(|_| format!("invalid number {arg} for {option}"))
+}
+
+/// Reads a world width and height, which have to be from 1 to world::MAX_SIZE.
+fn next_size(args: &mut slice::Iter<String>, option: &str) -> Result<[u16; 2], String> {
+	let width = next_number(args, option)?;
+	let height = next_number(args, option)?;
+	if width == 0 || height == 0 || width > world::MAX_SIZE || height > world::MAX_SIZE {
+		return Err(format!("world size {width}x{height} is out of range, it must be from 1x1 to {}x{}", world::MAX_SIZE, world::MAX_SIZE));
+	}
+	Ok([width, height])
+}
+
+fn parse_tool(name: &str) -> Result<interaction::TileInteraction, String> {
+	Ok(match name {
+		"grass" => interaction::TileInteraction::ReplaceGround(tile::Ground::Grass),
+		"water" => interaction::TileInteraction::ReplaceGround(tile::Ground::Water),
+		"bricks" => interaction::TileInteraction::ReplaceGround(tile::Ground::Bricks),
+		"gravel" => interaction::TileInteraction::ReplaceGround(tile::Ground::Gravel),
+		"leaf_litter" => interaction::TileInteraction::ReplaceGround(tile::Ground::LeafLitter),
+		"swamp" => interaction::TileInteraction::ReplaceGround(tile::Ground::Swamp),
+		"sand" => interaction::TileInteraction::ReplaceGround(tile::Ground::Sand),
+		"ash" => interaction::TileInteraction::ReplaceGround(tile::Ground::Ash),
+		"tree" => interaction::TileInteraction::BuildCover(tile::Cover::Tree(tile::TreeStage::Mature)),
+		"residential" => interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Residential, direction::Direction4::South)),
+		"commercial" => interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Commercial, direction::Direction4::South)),
+		"industrial" => interaction::TileInteraction::BuildCover(tile::Cover::Zone(tile::Zone::Industrial, direction::Direction4::South)),
+		"bomb" => interaction::TileInteraction::DemolishCover,
+		"fire" => interaction::TileInteraction::Ignite,
+		"town_hall" => interaction::TileInteraction::BuildStructure(tile::Structure::TownHall, direction::Direction4::South),
+		"warehouse" => interaction::TileInteraction::BuildStructure(tile::Structure::Warehouse, direction::Direction4::South),
+		"connect_road" => interaction::TileInteraction::ConnectRoad(tile::Road::Gravel),
+		_ => return Err(format!("unknown tool {name}")),
+	})
+}
+
+fn parse_interaction(tool: &str, shape: &str, brush_radius: u16) -> Result<interaction::InteractionShape, String> {
+	Ok(match (tool, shape) {
+		("road" | "dirt_path" | "paved_road" | "highway", "line" | "corner" | "route") => {
+			let road = match tool {
+				"road" => tile::Road::Gravel,
+				"dirt_path" => tile::Road::DirtPath,
+				"paved_road" => tile::Road::Paved,
+				_ => tile::Road::Highway,
+			};
+			let drag = match shape {
+				"line" => interaction::RoadDrag::Straight,
+				"corner" => interaction::RoadDrag::Corner { is_north_south_first: false },
+				_ => interaction::RoadDrag::Routed,
+			};
+			interaction::InteractionShape::RoadLine(road, drag)
+		}
+		("upgrade_road", "line") => interaction::InteractionShape::ChangeRoadLine(interaction::RoadChange::Upgrade),
+		("downgrade_road", "line") => interaction::InteractionShape::ChangeRoadLine(interaction::RoadChange::Downgrade),
+		("erase_road", "line") => interaction::InteractionShape::EraseRoadLine,
+		("road" | "dirt_path" | "paved_road" | "highway", _) => return Err(format!("the {tool} tool can only be used with the line, corner or route shapes")),
+		("upgrade_road" | "downgrade_road" | "erase_road", _) => return Err(format!("the {tool} tool can only be used with the line shape")),
+		(_, "dot") => interaction::InteractionShape::Dot(parse_tool(tool)?),
+		(_, "brush") => interaction::InteractionShape::Brush(parse_tool(tool)?, brush_radius),
+		(_, "ellipse") => interaction::InteractionShape::Ellipse(parse_tool(tool)?),
+		(_, "ellipse_outline") => interaction::InteractionShape::EllipseOutline(parse_tool(tool)?),
+		(_, "rectangle") => interaction::InteractionShape::Rectangle(parse_tool(tool)?),
+		(_, "line" | "corner" | "route") => return Err(format!("the {shape} shape can only be used with road tools, not {tool}")),
+		_ => return Err(format!("unknown shape {shape}")),
+	})
+}
+
+/// Reads a position and checks that it is inside the world.
+fn next_pos(args: &mut slice::Iter<String>, world: &world::World) -> Result<[u16; 2], String> {
+	let x: u16 = next_number(args, "--interact")?;
+	let y: u16 = next_number(args, "--interact")?;
+	if x >= world.get_width() || y >= world.get_height() {
+		return Err(format!("position {x}, {y} is outside the {}x{} world", world.get_width(), world.get_height()));
+	}
+	Ok([x, y])
+}
+
+fn print_road_network(world: &world::World) {
+	let road_network = &world.road_network;
+	println!("{} nodes, {} edges", road_network.get_nodes().len(), road_network.get_edges().len());
+	for (id, edge) in road_network.get_edges() {
+		let [[start_x, start_y], [end_x, end_y]] = edge.get_ends();
+		println!("Edge {id}: {start_x}, {start_y} to {end_x}, {end_y}, {} tiles of {}", edge.get_length(), edge.road.get_name());
+	}
+	for (index, component) in road_network.get_components().iter().enumerate() {
+		let [x, y] = component.nodes[0];
+		println!("Component {index}: {} nodes and {} edges, including {x}, {y}", component.nodes.len(), component.edges.len());
+	}
+}
+
+/// Runs the options given on the command line, returns an error message if one fails.
+pub fn run(args: &[String]) -> Result<(), String> {
+	let mut world = world::World::new(256, 256);
+	let mut args = args.iter();
+	// Quarter turns clockwise from facing south for the buildings of interactions
+	let mut rotation = 0;
+	let mut brush_radius = interaction::DEFAULT_BRUSH_RADIUS;
+	while let Some(option) = args.next() {
+		match option.as_str() {
+			"--new" => {
+				let [width, height] = next_size(&mut args, option)?;
+				world = world::World::new(width, height);
+			}
+			"--generate" => {
+				let seed = next_number(&mut args, option)?;
+				let [width, height] = next_size(&mut args, option)?;
+				world = terrain::generate(width, height, &terrain::TerrainParameters { seed, ..Default::default() });
+			}
+			"--load" => {
+				let path = next_arg(&mut args, option)?;
+				world = world::World::load(path::Path::new(path)).map_err(|error| format!("failed to load {path}: {error}"))?;
+			}
+			"--import" => {
+				let path = next_arg(&mut args, option)?;
+				let import = import::import(path::Path::new(path), &import::get_default_palette())
+					.map_err(|error| format!("failed to import {path}: {error}"))?;
+				for [x, y] in import.skipped_covers {
+					println!("Skipped cover at {x}, {y} that cannot go on its ground or has nothing to join");
+				}
+				world = import.world;
+			}
+			"--interact" => {
+				let tool = next_arg(&mut args, option)?;
+				let shape = next_arg(&mut args, option)?;
+				let mut interaction = parse_interaction(tool, shape, brush_radius)?;
+				for _ in 0..rotation {
+					interaction.rotate();
+				}
+				let click_start = next_pos(&mut args, &world)?;
+				// The second position is optional, peek to see if there is one
+				let pos = match args.as_slice().first().is_some_and(|arg| !arg.starts_with("--")) {
+					true => next_pos(&mut args, &world)?,
+					false => click_start,
+				};
+				let mouse = mouse::Mouse { pos, click_start, is_left_clicking: false, is_middle_clicking: false, is_right_clicking: false, gui_pos: [0; 2] };
+				if let Err(error) = world.interact(&interaction, &mouse) {
+					println!("{error}");
+				}
+			}
+			"--rotate" => rotation = (rotation + 1) % 4,
+			"--radius" => {
+				brush_radius = next_number(&mut args, option)?;
+				if brush_radius > interaction::MAX_BRUSH_RADIUS {
+					return Err(format!("brush radius {brush_radius} is too big, the largest is {}", interaction::MAX_BRUSH_RADIUS));
+				}
+			}
+			"--ticks" => {
+				let count: u64 = next_number(&mut args, option)?;
+				for _ in 0..count {
+					for event in world.tick() {
+						println!("Tick {}: {event}", world.tick_count);
+					}
+				}
+			}
+			"--roads" => print_road_network(&world),
+			"--check-roads" => match world.road_network.matches(&road_network::RoadNetwork::new(&world.tiles)) {
+				true => println!("Road network matches the tiles"),
+				false => return Err("road network does not match one rebuilt from the tiles".to_string()),
+			},
+			"--sandbox" => world.is_sandbox = match next_arg(&mut args, option)? {
+				"on" => true,
+				"off" => false,
+				value => return Err(format!("invalid value {value} for {option}, expected on or off")),
+			},
+			"--money" => println!("${}", world.money),
+			"--zones" => {
+				let counts = zoning::count_buildings(&world.tiles);
+				let demand = zoning::get_demand(&world.zone_parameters, counts);
+				for zone in tile::Zone::ALL {
+					println!("{}: {} buildings, demand {:.2}", zone.get_name(), counts[zone.get_index()], demand[zone.get_index()]);
+				}
+			}
+			"--vehicles" => {
+				println!("{} vehicles", world.vehicles.len());
+				for vehicle in &world.vehicles {
+					let [x, y] = vehicle.pos;
+					let [destination_x, destination_y] = vehicle.get_destination();
+					println!("Vehicle at {x}, {y} going to {destination_x}, {destination_y}");
+				}
+			}
+			"--path" => {
+				let mode = match next_arg(&mut args, option)? {
+					"road" => pathfinding::PathMode::RoadOnly,
+					"offroad" => pathfinding::PathMode::OffRoad,
+					"newroad" => pathfinding::PathMode::NewRoad,
+					mode => return Err(format!("unknown path mode {mode}")),
+				};
+				let start = next_pos(&mut args, &world)?;
+				let goal = next_pos(&mut args, &world)?;
+				match pathfinding::find_path(&world.tiles, start, goal, mode) {
+					Some(path) => {
+						let tiles: Vec<String> = path.tiles.iter().map(|[x, y]| format!("{x},{y}")).collect();
+						println!("Cost {}: {}", path.cost, tiles.join(" "));
+					}
+					None => println!("No path"),
+				}
+			}
+			"--save" => {
+				let path = next_arg(&mut args, option)?;
+				world.save(path::Path::new(path)).map_err(|error| format!("failed to save {path}: {error}"))?;
+			}
+			"--export" => {
+				let path = next_arg(&mut args, option)?;
+				let mode = match args.as_slice().first().map(String::as_str) {
+					Some("full") => {
+						args.next();
+						export::ExportMode::Full
+					}
+					_ => export::ExportMode::Minimap,
+				};
+				let image = export::export(&world, mode).map_err(|error| format!("failed to export {path}: {error}"))?;
+				image.save(path).map_err(|error| format!("failed to export {path}: {error}"))?;
+			}
+			"--help" => println!("{USAGE}"),
+			_ => return Err(format!("unknown option {option}\n{USAGE}")),
+		}
+	}
+	Ok(())
+}
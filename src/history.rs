@@ -0,0 +1,97 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use super::economy;
+use super::tile;
+use super::world;
+
+/// The state of every tile changed by one interaction, from before the interaction.
+pub struct Action {
+	tiles: Vec<([u16; 2], tile::Tile)>,
+	/// Money paid for the interaction, given back when it is undone.
+	pub cost: i64,
+}
+
+impl Action {
+	/// Compares the states of tiles from before an interaction with what they are now, returns None if nothing changed.
+	pub fn from_difference(tiles_before: Vec<([u16; 2], tile::Tile)>, tiles: &ndarray::Array2<tile::Tile>) -> Option<Self> {
+		let tiles: Vec<([u16; 2], tile::Tile)> = tiles_before.into_iter()
+			.filter(|(pos, tile)| tiles[[pos[0] as usize, pos[1] as usize]] != *tile)
+			.collect();
+		match tiles.is_empty() {
+			true => None,
+			false => Some(Self { tiles, cost: 0 }),
+		}
+	}
+
+	/// Adds an action that came straight after this one, so that both are undone together.
+	pub fn merge(&mut self, later: Self) {
+		let positions: BTreeSet<[u16; 2]> = self.get_positions().into_iter().collect();
+		// Keep the oldest state of tiles that both actions changed
+		self.tiles.extend(later.tiles.into_iter().filter(|(pos, _)| !positions.contains(pos)));
+		self.cost += later.cost;
+	}
+
+	/// Gets the positions of the tiles changed by the action.
+	pub fn get_positions(&self) -> Vec<[u16; 2]> {
+		self.tiles.iter().map(|(pos, _)| *pos).collect()
+	}
+
+	/// Restores the stored tiles and returns an action that reverses that.
+	fn apply(self, world: &mut world::World) -> Self {
+		let tiles: Vec<([u16; 2], tile::Tile)> = self.tiles.into_iter().map(|(pos, tile)| {
+			let old_tile = std::mem::replace(&mut world.tiles[[pos[0] as usize, pos[1] as usize]], tile);
+			(pos, old_tile)
+		}).collect();
+		let out = Self { tiles, cost: self.cost };
+		world.on_tiles_changed(&out.get_positions());
+		out
+	}
+}
+
+pub struct History {
+	undo_actions: VecDeque<Action>,
+	redo_actions: Vec<Action>,
+	max_size: usize,
+}
+
+impl History {
+	/// Creates a history that remembers at most max_size actions to undo.
+	pub fn new(max_size: usize) -> Self {
+		Self { undo_actions: VecDeque::new(), redo_actions: Vec::new(), max_size }
+	}
+
+	pub fn push(&mut self, action: Action) {
+		self.redo_actions.clear();
+		self.undo_actions.push_back(action);
+		while self.undo_actions.len() > self.max_size {
+			self.undo_actions.pop_front();
+		}
+	}
+
+	/// Forgets all actions, used when the tiles they refer to no longer exist.
+	pub fn clear(&mut self) {
+		self.undo_actions.clear();
+		self.redo_actions.clear();
+	}
+
+	pub fn undo(&mut self, world: &mut world::World) {
+		if let Some(action) = self.undo_actions.pop_back() {
+			world.money += action.cost;
+			self.redo_actions.push(action.apply(world));
+		}
+	}
+
+	/// Redoes the last undone action, it is kept to try again later if it cannot be afforded.
+	pub fn redo(&mut self, world: &mut world::World) -> Result<(), economy::CannotAfford> {
+		if let Some(action) = self.redo_actions.pop() {
+			if action.cost > world.money {
+				let error = economy::CannotAfford { cost: action.cost, money: world.money };
+				self.redo_actions.push(action);
+				return Err(error);
+			}
+			world.money -= action.cost;
+			self.undo_actions.push_back(action.apply(world));
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,182 @@
+// Builds worlds from images drawn in image editors
+
+use std::{fmt, path};
+
+use super::direction;
+use super::road_network;
+use super::tile;
+use super::world;
+
+/// What a pixel color becomes in an imported world.
+#[derive(Copy, Clone)]
+pub struct PaletteEntry {
+	pub color: [u8; 3],
+	pub ground: tile::Ground,
+	pub cover: tile::Cover,
+}
+
+/// Gets a palette that matches the colors of minimap exports.
+pub fn get_default_palette() -> Vec<PaletteEntry> {
+	let grounds = [
+		tile::Ground::Grass, tile::Ground::Water, tile::Ground::Bricks, tile::Ground::Gravel,
+		tile::Ground::LeafLitter, tile::Ground::Swamp, tile::Ground::Sand, tile::Ground::Ash,
+	];
+	let mut palette: Vec<PaletteEntry> = grounds.iter()
+		.map(|ground| PaletteEntry { color: ground.get_map_color(), ground: *ground, cover: tile::Cover::None })
+		.collect();
+	palette.push(PaletteEntry { color: tile::Cover::Tree(tile::TreeStage::Mature).get_map_color().unwrap(), ground: tile::Ground::Grass, cover: tile::Cover::Tree(tile::TreeStage::Mature) });
+	for zone in tile::Zone::ALL {
+		for (ground, cover) in [(tile::Ground::Grass, tile::Cover::Zone(zone, direction::Direction4::South)), (tile::Ground::Bricks, tile::Cover::Building(zone, direction::Direction4::South))] {
+			palette.push(PaletteEntry { color: cover.get_map_color().unwrap(), ground, cover });
+		}
+	}
+	// Roads, bridges and structures are joined up with their neighbors after the pixels are read
+	for (ground, cover) in [
+		(tile::Ground::Ash, tile::Cover::Rubble),
+		(tile::Ground::Grass, tile::Cover::Road([tile::Road::Gravel; 8])),
+		(tile::Ground::Water, tile::Cover::Bridge(direction::Direction2::NorthSouth, tile::Road::Gravel)),
+		(tile::Ground::Bricks, tile::Cover::Structure { structure: tile::Structure::TownHall, facing: direction::Direction4::South, part: [0, 0] }),
+		(tile::Ground::Bricks, tile::Cover::Structure { structure: tile::Structure::Warehouse, facing: direction::Direction4::South, part: [0, 0] }),
+	] {
+		palette.push(PaletteEntry { color: cover.get_map_color().unwrap(), ground, cover });
+	}
+	palette
+}
+
+/// Gets if a road or bridge on a neighboring tile runs towards a tile.
+fn is_road_towards(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], direction: direction::Direction4) -> bool {
+	match direction.offset_pos(pos, [tiles.dim().0 as u16, tiles.dim().1 as u16]) {
+		Some([x, y]) => match tiles[[x as usize, y as usize]].cover {
+			tile::Cover::Road(_) => true,
+			tile::Cover::Bridge(axis, _) => axis == direction.get_axis(),
+			_ => false,
+		},
+		None => false,
+	}
+}
+
+/// Turns bridges along the roads and bridges next to them, then links each road to its neighbors.
+/// Roads with no neighbors to link to are removed.
+fn join_roads(tiles: &mut ndarray::Array2<tile::Tile>, skipped_covers: &mut Vec<[u16; 2]>) {
+	let size = [tiles.dim().0 as u16, tiles.dim().1 as u16];
+	for y in 0..size[1] {
+		for x in 0..size[0] {
+			if let tile::Cover::Bridge(_, road) = tiles[[x as usize, y as usize]].cover {
+				let is_north_south = [direction::Direction4::North, direction::Direction4::South].into_iter()
+					.filter_map(|direction| direction.offset_pos([x, y], size))
+					.any(|[neighbor_x, neighbor_y]| matches!(tiles[[neighbor_x as usize, neighbor_y as usize]].cover, tile::Cover::Road(_) | tile::Cover::Bridge(..)));
+				let axis = match is_north_south {
+					true => direction::Direction2::NorthSouth,
+					false => direction::Direction2::EastWest,
+				};
+				tiles[[x as usize, y as usize]].cover = tile::Cover::Bridge(axis, road);
+			}
+		}
+	}
+	for y in 0..size[1] {
+		for x in 0..size[0] {
+			let road_quarters = match tiles[[x as usize, y as usize]].cover {
+				tile::Cover::Road(valid) => valid,
+				_ => continue,
+			};
+			let mut joined_road_quarters = [tile::Road::None; 8];
+			for direction in (0..4).map(direction::Direction4::new) {
+				if is_road_towards(tiles, [x, y], direction) {
+					let index = direction::Direction8::from(direction).get_int_val() as usize;
+					joined_road_quarters[index] = road_quarters[index];
+				}
+			}
+			tiles[[x as usize, y as usize]].cover = match joined_road_quarters.iter().all(|road_quarter| *road_quarter == tile::Road::None) {
+				true => {
+					skipped_covers.push([x, y]);
+					tile::Cover::None
+				}
+				false => tile::Cover::Road(joined_road_quarters),
+			};
+		}
+	}
+}
+
+/// Places structures over blocks of pixels of their color, starting from the top left of each block.
+/// Pixels that do not make up a whole footprint are removed.
+fn place_structures(tiles: &mut ndarray::Array2<tile::Tile>, skipped_covers: &mut Vec<[u16; 2]>) {
+	let size = [tiles.dim().0 as u16, tiles.dim().1 as u16];
+	let mut is_placed = ndarray::Array2::from_elem(tiles.dim(), false);
+	for y in 0..size[1] {
+		for x in 0..size[0] {
+			let structure = match tiles[[x as usize, y as usize]].cover {
+				tile::Cover::Structure { structure, .. } if !is_placed[[x as usize, y as usize]] => structure,
+				_ => continue,
+			};
+			// Map colors do not show which way a structure faced, so try it facing south and then turned
+			let footprint = [direction::Direction4::South, direction::Direction4::East].into_iter().find_map(|facing| {
+				let footprint = structure.get_footprint(facing, [x, y], size)?;
+				let is_whole = footprint.iter().all(|([part_x, part_y], _)| {
+					let part_index = [*part_x as usize, *part_y as usize];
+					!is_placed[part_index] && matches!(tiles[part_index].cover, tile::Cover::Structure { structure: part_structure, .. } if part_structure == structure)
+				});
+				is_whole.then_some((facing, footprint))
+			});
+			match footprint {
+				Some((facing, valid)) => for ([part_x, part_y], part) in valid {
+					tiles[[part_x as usize, part_y as usize]].cover = tile::Cover::Structure { structure, facing, part };
+					is_placed[[part_x as usize, part_y as usize]] = true;
+				},
+				None => {
+					tiles[[x as usize, y as usize]].cover = tile::Cover::None;
+					skipped_covers.push([x, y]);
+				}
+			}
+		}
+	}
+}
+
+pub enum ImportError {
+	Image(image::ImageError),
+	TooLarge { width: u32, height: u32 },
+	UnknownColor { pos: [u32; 2], color: [u8; 3] },
+}
+
+impl fmt::Display for ImportError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Image(error) => write!(f, "{error}"),
+			Self::TooLarge { width, height } => write!(f, "image is {width}x{height}, worlds can be at most {}x{}", world::MAX_SIZE, world::MAX_SIZE),
+			Self::UnknownColor { pos: [x, y], color: [red, green, blue] } =>
+				write!(f, "pixel at {x}, {y} has color #{red:02X}{green:02X}{blue:02X} which is not in the palette"),
+		}
+	}
+}
+
+/// An imported world along with the tiles where the cover from the palette could not go on the ground or be joined up.
+pub struct Import {
+	pub world: world::World,
+	pub skipped_covers: Vec<[u16; 2]>,
+}
+
+/// Creates a world the size of an image with each pixel mapped to a tile by the palette.
+pub fn import(path: &path::Path, palette: &[PaletteEntry]) -> Result<Import, ImportError> {
+	let image = image::open(path).map_err(ImportError::Image)?.to_rgb8();
+	let (width, height) = image.dimensions();
+	if width > world::MAX_SIZE as u32 || height > world::MAX_SIZE as u32 {
+		return Err(ImportError::TooLarge { width, height });
+	}
+	let mut world = world::World::new(width as u16, height as u16);
+	let mut skipped_covers = Vec::new();
+	for (x, y, pixel) in image.enumerate_pixels() {
+		let entry = match palette.iter().find(|entry| entry.color == pixel.0) {
+			Some(valid) => valid,
+			None => return Err(ImportError::UnknownColor { pos: [x, y], color: pixel.0 }),
+		};
+		let tile = &mut world.tiles[[x as usize, y as usize]];
+		tile.ground = entry.ground;
+		match entry.cover.can_go_on_ground(entry.ground) {
+			true => tile.cover = entry.cover,
+			false => skipped_covers.push([x as u16, y as u16]),
+		}
+	}
+	join_roads(&mut world.tiles, &mut skipped_covers);
+	place_structures(&mut world.tiles, &mut skipped_covers);
+	world.road_network = road_network::RoadNetwork::new(&world.tiles);
+	Ok(Import { world, skipped_covers })
+}
@@ -1,6 +1,9 @@
+use std::collections::BTreeSet;
+
 use crate::direction;
-use crate::{texture, vertex};
+use crate::{pathfinding, texture, vertex};
 
+use super::economy;
 use super::tile;
 use super::mouse;
 
@@ -9,25 +12,196 @@ pub enum TileInteraction {
 	ReplaceGround(tile::Ground),
 	BuildCover(tile::Cover),
 	DemolishCover,
-	BuildRoad([tile::Road; 4]),
+	BuildRoad([tile::Road; 8]),
+	Ignite,
+	/// Builds a structure with its top left on the tile, turned to face a side.
+	BuildStructure(tile::Structure, direction::Direction4),
+	/// Builds a bridge carrying a road along an axis over water or swamp.
+	BuildBridge(direction::Direction2, tile::Road),
+	/// Upgrades or downgrades the road quarters that are set, or the road on a bridge that runs along them.
+	ChangeRoad(RoadChange, [bool; 8]),
+	/// Builds a road on one tile joined to every neighboring road, filling in the neighbors' quarters that face it.
+	ConnectRoad(tile::Road),
+	/// Removes the road quarters that are set, or a bridge that runs along them, leaving no cover once every quarter is gone.
+	EraseRoad([bool; 8]),
+}
+
+#[derive(Copy, Clone)]
+pub enum RoadChange {
+	Upgrade,
+	Downgrade,
+}
+
+impl RoadChange {
+	/// Gets what a road becomes, None if it cannot be changed.
+	fn apply(self, road: tile::Road) -> Option<tile::Road> {
+		match self {
+			RoadChange::Upgrade => road.get_upgrade(),
+			RoadChange::Downgrade => road.get_downgrade(),
+		}
+	}
+
+	/// Gets if changing the road quarters that are set would change anything on a tile.
+	fn can_change(self, tile: &tile::Tile, road_mask: [bool; 8]) -> bool {
+		match tile.cover.get_road_quarters() {
+			Some(road_quarters) => road_quarters.iter().zip(road_mask).any(|(road_quarter, is_set)| is_set && self.apply(*road_quarter).is_some()),
+			None => false,
+		}
+	}
+}
+
+/// Longest run of water or swamp tiles that a bridge can cross.
+pub const MAX_BRIDGE_LENGTH: usize = 8;
+/// Radius of a brush when it is first picked.
+pub const DEFAULT_BRUSH_RADIUS: u16 = 2;
+pub const MAX_BRUSH_RADIUS: u16 = 16;
+
+/// Removes a tile's cover, setting it on fire if it could burn.
+fn demolish_tile(tile: &mut tile::Tile) {
+	// Blowing up something that can burn sets the tile on fire
+	if tile.cover.is_flammable() && tile.fire == 0 {
+		tile.fire = 1;
+	}
+	tile.cover = tile::Cover::None;
+}
+
+/// Gets the positions of the parts of a placed structure that are still there, from the position of any one of its parts.
+fn get_placed_structure_tiles(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Option<Vec<[u16; 2]>> {
+	let (structure, facing, part) = match tiles[[pos[0] as usize, pos[1] as usize]].cover {
+		tile::Cover::Structure { structure, facing, part } => (structure, facing, part),
+		_ => return None,
+	};
+	Some(structure.get_placed_footprint(facing, pos, part, get_size(tiles))?.into_iter()
+		.filter(|(part_pos, part)| tiles[[part_pos[0] as usize, part_pos[1] as usize]].cover == tile::Cover::Structure { structure, facing, part: *part })
+		.map(|(part_pos, _)| part_pos)
+		.collect())
+}
+
+fn get_size(tiles: &ndarray::Array2<tile::Tile>) -> [u16; 2] {
+	[tiles.dim().0 as u16, tiles.dim().1 as u16]
+}
+
+/// Gets the neighbors of a tile that a road built on it would join, which are roads and bridges that run towards it.
+/// A diagonal neighbor is only joined when neither tile beside it is, so that a road going past is not joined twice.
+fn get_road_connections(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Vec<(direction::Direction8, [u16; 2])> {
+	let get_connection = |direction: direction::Direction8| {
+		let neighbor_pos = direction.offset_pos(pos, get_size(tiles))?;
+		let neighbor_cover = tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]].cover;
+		let is_joined = match neighbor_cover {
+			tile::Cover::Road(_) => true,
+			tile::Cover::Bridge(..) => neighbor_cover.get_road_quarters()
+				.is_some_and(|road_quarters| road_quarters[direction.get_opposite().get_int_val() as usize] != tile::Road::None),
+			_ => false,
+		};
+		is_joined.then_some(neighbor_pos)
+	};
+	(0..8)
+		.map(direction::Direction8::new)
+		.filter(|direction| !direction.is_diagonal() || [7, 1]
+			.map(|turn| direction::Direction8::new((direction.get_int_val() + turn) % 8))
+			.into_iter()
+			.all(|beside| get_connection(beside).is_none()))
+		.filter_map(|direction| Some((direction, get_connection(direction)?)))
+		.collect()
 }
 
 impl TileInteraction {
-	pub fn interact(&self, tile: &mut tile::Tile) {
+	pub fn interact(&self, tiles: &mut ndarray::Array2<tile::Tile>, pos: [u16; 2]) {
+		match self {
+			TileInteraction::BuildStructure(structure, facing) => {
+				let footprint = match structure.get_footprint(*facing, pos, get_size(tiles)) {
+					Some(valid) => valid,
+					None => return,
+				};
+				if footprint.iter().any(|(part_pos, _)| !Self::can_build_structure_on(&tiles[[part_pos[0] as usize, part_pos[1] as usize]], *structure, *facing)) {
+					return;
+				}
+				for (part_pos, part) in footprint {
+					tiles[[part_pos[0] as usize, part_pos[1] as usize]].cover = tile::Cover::Structure { structure: *structure, facing: *facing, part };
+				}
+			}
+			// Water sinks to the height of its lowest neighbor so that it does not keep flowing downhill from where it was painted
+			TileInteraction::ReplaceGround(tile::Ground::Water) => {
+				self.interact_tile(&mut tiles[[pos[0] as usize, pos[1] as usize]]);
+				if tiles[[pos[0] as usize, pos[1] as usize]].ground == tile::Ground::Water {
+					let lowest_neighbor_height = (0..4)
+						.filter_map(|direction| direction::Direction4::new(direction).offset_pos(pos, get_size(tiles)))
+						.map(|[x, y]| tiles[[x as usize, y as usize]].height)
+						.min();
+					let tile = &mut tiles[[pos[0] as usize, pos[1] as usize]];
+					tile.height = tile.height.min(lowest_neighbor_height.unwrap_or(tile.height));
+				}
+			}
+			// Demolishing any part of a structure demolishes all of it
+			TileInteraction::DemolishCover => match get_placed_structure_tiles(tiles, pos) {
+				Some(part_positions) => for part_pos in part_positions {
+					demolish_tile(&mut tiles[[part_pos[0] as usize, part_pos[1] as usize]]);
+				},
+				None => demolish_tile(&mut tiles[[pos[0] as usize, pos[1] as usize]]),
+			},
+			TileInteraction::ConnectRoad(road) => {
+				let connections = get_road_connections(tiles, pos);
+				if connections.is_empty() || !can_build_road_on(&tiles[[pos[0] as usize, pos[1] as usize]]) {
+					return;
+				}
+				let mut road_quarters = [tile::Road::None; 8];
+				for (direction, neighbor_pos) in connections {
+					road_quarters[direction.get_int_val() as usize] = *road;
+					if let tile::Cover::Road(neighbor_road_quarters) = &mut tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]].cover {
+						let neighbor_road_quarter = &mut neighbor_road_quarters[direction.get_opposite().get_int_val() as usize];
+						if *neighbor_road_quarter == tile::Road::None {
+							*neighbor_road_quarter = *road;
+						}
+					}
+				}
+				TileInteraction::BuildRoad(road_quarters).interact_tile(&mut tiles[[pos[0] as usize, pos[1] as usize]]);
+			}
+			_ => self.interact_tile(&mut tiles[[pos[0] as usize, pos[1] as usize]]),
+		}
+	}
+
+	/// Gets the tiles that interacting at a position could change.
+	fn get_affected_tiles(&self, tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Vec<[u16; 2]> {
+		match self {
+			TileInteraction::BuildStructure(structure, facing) => structure.get_footprint(*facing, pos, get_size(tiles))
+				.map_or(Vec::new(), |footprint| footprint.into_iter().map(|(part_pos, _)| part_pos).collect()),
+			TileInteraction::DemolishCover => get_placed_structure_tiles(tiles, pos).unwrap_or(vec![pos]),
+			TileInteraction::ConnectRoad(_) => std::iter::once(pos)
+				.chain(get_road_connections(tiles, pos).into_iter().map(|(_, neighbor_pos)| neighbor_pos))
+				.collect(),
+			_ => vec![pos],
+		}
+	}
+
+	/// Turns what the interaction builds a quarter turn clockwise if it has a facing.
+	pub fn rotate(&mut self) {
+		match self {
+			TileInteraction::BuildCover(cover) => cover.rotate(),
+			TileInteraction::BuildStructure(_, facing) => *facing = facing.get_clockwise(),
+			_ => {},
+		}
+	}
+
+	fn can_build_structure_on(tile: &tile::Tile, structure: tile::Structure, facing: direction::Direction4) -> bool {
+		can_build_cover_on(tile, tile::Cover::Structure { structure, facing, part: [0, 0] })
+	}
+
+	/// Applies the interactions that only change one tile.
+	fn interact_tile(&self, tile: &mut tile::Tile) {
 		match self {
 			TileInteraction::ReplaceGround(ground) => {
-				if tile.cover.can_go_on_ground(*ground) {
+				if can_replace_ground(tile, *ground) {
 					tile.ground = *ground
 				}
 			},
 			TileInteraction::BuildCover(cover) => {
-				if matches!(tile.cover, tile::Cover::None) && cover.can_go_on_ground(tile.ground) {
+				if can_build_cover_on(tile, *cover) {
 					tile.cover = *cover;
 				}
 			},
-			TileInteraction::DemolishCover => tile.cover = tile::Cover::None,
+			TileInteraction::DemolishCover => demolish_tile(tile),
 			TileInteraction::BuildRoad(road_quarters) => {
-				if !tile::Cover::Road([tile::Road::None; 4]).can_go_on_ground(tile.ground) {
+				if !can_build_road_on(tile) {
 					return
 				}
 				match &mut tile.cover {
@@ -42,21 +216,109 @@ impl TileInteraction {
 					_ => {},
 				}
 			},
+			TileInteraction::Ignite => {
+				if tile.cover.is_flammable() && tile.fire == 0 {
+					tile.fire = 1;
+				}
+			},
+			TileInteraction::BuildStructure(..) | TileInteraction::ConnectRoad(_) => {},
+			TileInteraction::BuildBridge(axis, road) => {
+				if tile.cover == tile::Cover::None && can_build_bridge_on(tile, *axis) {
+					tile.cover = tile::Cover::Bridge(*axis, *road);
+				}
+			},
+			TileInteraction::ChangeRoad(change, road_mask) => {
+				if !change.can_change(tile, *road_mask) {
+					return;
+				}
+				match &mut tile.cover {
+					tile::Cover::Road(road_quarters) => {
+						for (road_quarter, is_set) in road_quarters.iter_mut().zip(road_mask) {
+							if *is_set {
+								*road_quarter = change.apply(*road_quarter).unwrap_or(*road_quarter);
+							}
+						}
+					},
+					tile::Cover::Bridge(_, road) => *road = change.apply(*road).unwrap_or(*road),
+					_ => {},
+				}
+			},
+			TileInteraction::EraseRoad(road_mask) => {
+				if !can_erase_road(tile, *road_mask) {
+					return;
+				}
+				match &mut tile.cover {
+					tile::Cover::Road(road_quarters) => {
+						for (road_quarter, is_set) in road_quarters.iter_mut().zip(road_mask) {
+							if *is_set {
+								*road_quarter = tile::Road::None;
+							}
+						}
+						if road_quarters.iter().all(|road_quarter| *road_quarter == tile::Road::None) {
+							tile.cover = tile::Cover::None;
+						}
+					},
+					tile::Cover::Bridge(..) => tile.cover = tile::Cover::None,
+					_ => {},
+				}
+			},
+		}
+	}
+
+	pub fn generate_select_tris(&self, tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Vec<vertex::Vertex> {
+		match self {
+			// Mark every tile of the footprint, the parts outside the world cannot be shown
+			TileInteraction::BuildStructure(structure, facing) => {
+				let size = get_size(tiles);
+				let [width, height] = structure.get_size(*facing);
+				let is_in_world = structure.get_footprint(*facing, pos, size).is_some();
+				let mut tris = Vec::new();
+				for y in pos[1]..(pos[1] + height as u16).min(size[1]) {
+					for x in pos[0]..(pos[0] + width as u16).min(size[0]) {
+						match is_in_world && Self::can_build_structure_on(&tiles[[x as usize, y as usize]], *structure, *facing) {
+							true => tris.extend(texture::Texture::SelectBuildable.generate_tris([x, y])),
+							false => tris.extend(texture::Texture::SelectUnbuildable.generate_tris([x, y])),
+						}
+					}
+				}
+				tris
+			}
+			// Show which way the cover will face
+			TileInteraction::BuildCover(tile::Cover::Zone(_, facing) | tile::Cover::Building(_, facing)) => {
+				let mut tris = self.generate_tile_select_tris(&tiles[[pos[0] as usize, pos[1] as usize]], pos).to_vec();
+				tris.extend(texture::generate_tris_from_id(texture::Texture4Directional::ZoneArrow.get_texture_id(*facing), pos));
+				tris
+			}
+			TileInteraction::DemolishCover => match get_placed_structure_tiles(tiles, pos) {
+				Some(part_positions) => part_positions.into_iter().flat_map(|part_pos| texture::Texture::SelectDestroy.generate_tris(part_pos)).collect(),
+				None => self.generate_tile_select_tris(&tiles[[pos[0] as usize, pos[1] as usize]], pos).to_vec(),
+			},
+			// Mark the neighbors that will be joined as well
+			TileInteraction::ConnectRoad(_) => {
+				let connections = get_road_connections(tiles, pos);
+				match !connections.is_empty() && can_build_road_on(&tiles[[pos[0] as usize, pos[1] as usize]]) {
+					true => std::iter::once(pos).chain(connections.into_iter().map(|(_, neighbor_pos)| neighbor_pos))
+						.flat_map(|select_pos| texture::Texture::SelectBuildable.generate_tris(select_pos))
+						.collect(),
+					false => texture::Texture::SelectUnbuildable.generate_tris(pos).to_vec(),
+				}
+			}
+			_ => self.generate_tile_select_tris(&tiles[[pos[0] as usize, pos[1] as usize]], pos).to_vec(),
 		}
 	}
 
-	pub fn generate_select_tris(&self, tile: &tile::Tile, pos: [u16; 2]) -> [vertex::Vertex; 6] {
+	fn generate_tile_select_tris(&self, tile: &tile::Tile, pos: [u16; 2]) -> [vertex::Vertex; 6] {
 		match self {
 			TileInteraction::ReplaceGround(ground) => {
-				match (*ground == tile.ground, tile.cover.can_go_on_ground(*ground)) {
+				match (*ground == tile.ground, can_replace_ground(tile, *ground)) {
 					(false, true) => texture::Texture::SelectBuildable,
 					_ => texture::Texture::SelectUnbuildable,
 				}.generate_tris(pos)
 			}
 			TileInteraction::BuildCover(cover) => {
-				match (tile.cover, cover.can_go_on_ground(tile.ground)) {
-					(tile::Cover::None, true) => texture::Texture::SelectBuildable,
-					_ => texture::Texture::SelectUnbuildable,
+				match can_build_cover_on(tile, *cover) {
+					true => texture::Texture::SelectBuildable,
+					false => texture::Texture::SelectUnbuildable,
 				}.generate_tris(pos)
 			},
 			TileInteraction::DemolishCover => {
@@ -66,111 +328,442 @@ impl TileInteraction {
 				}.generate_tris(pos)
 			}
 			TileInteraction::BuildRoad(_) => {
-				match (matches!(tile.cover, tile::Cover::None) || matches!(tile.cover, tile::Cover::Road(_))) &&
-				tile::Cover::Road([tile::Road::None; 4]).can_go_on_ground(tile.ground) {
+				match can_build_road_on(tile) {
+					true => texture::Texture::SelectBuildable,
+					false => texture::Texture::SelectUnbuildable,
+				}.generate_tris(pos)
+			},
+			TileInteraction::BuildBridge(axis, _) => {
+				match can_build_bridge_on(tile, *axis) {
+					true => texture::Texture::SelectBuildable,
+					false => texture::Texture::SelectUnbuildable,
+				}.generate_tris(pos)
+			},
+			TileInteraction::Ignite => {
+				match tile.cover.is_flammable() && tile.fire == 0 {
+					true => texture::Texture::SelectDestroy,
+					false => texture::Texture::SelectUnbuildable,
+				}.generate_tris(pos)
+			},
+			TileInteraction::BuildStructure(..) | TileInteraction::ConnectRoad(_) => texture::Texture::SelectUnbuildable.generate_tris(pos),
+			TileInteraction::ChangeRoad(change, road_mask) => {
+				match change.can_change(tile, *road_mask) {
 					true => texture::Texture::SelectBuildable,
 					false => texture::Texture::SelectUnbuildable,
 				}.generate_tris(pos)
 			},
+			TileInteraction::EraseRoad(road_mask) => {
+				match can_erase_road(tile, *road_mask) {
+					true => texture::Texture::SelectDestroy,
+					false => texture::Texture::SelectUnbuildable,
+				}.generate_tris(pos)
+			},
+		}
+	}
+}
+
+/// How a road drag gets from the click start to the mouse.
+#[derive(Copy, Clone, PartialEq)]
+pub enum RoadDrag {
+	/// A straight line along an axis or diagonal.
+	Straight,
+	/// Two straight lines that meet at a corner.
+	Corner { is_north_south_first: bool },
+	/// The cheapest route around water and buildings.
+	Routed,
+}
+
+impl RoadDrag {
+	/// Gets the next drag mode, to cycle through them.
+	pub fn get_next(self) -> Self {
+		match self {
+			RoadDrag::Straight => RoadDrag::Corner { is_north_south_first: false },
+			RoadDrag::Corner { .. } => RoadDrag::Routed,
+			RoadDrag::Routed => RoadDrag::Straight,
+		}
+	}
+
+	pub fn get_name(self) -> &'static str {
+		match self {
+			RoadDrag::Straight => "straight",
+			RoadDrag::Corner { is_north_south_first: false } => "corner, east or west first",
+			RoadDrag::Corner { is_north_south_first: true } => "corner, north or south first",
+			RoadDrag::Routed => "routed",
+		}
+	}
+
+	/// Gets the tiles in order from the click start to the mouse, empty if a route cannot be found.
+	fn get_tiles(self, tiles: &ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse) -> Vec<[u16; 2]> {
+		let start = mouse.click_start;
+		match self {
+			RoadDrag::Straight => get_line_tiles(mouse),
+			RoadDrag::Corner { is_north_south_first } => {
+				let corner = match is_north_south_first {
+					true => [start[0], mouse.pos[1]],
+					false => [mouse.pos[0], start[1]],
+				};
+				let mut out = get_leg_tiles(start, corner);
+				out.extend(get_leg_tiles(corner, mouse.pos).into_iter().skip(1));
+				out
+			}
+			RoadDrag::Routed => match pathfinding::find_path(tiles, start, mouse.pos, pathfinding::PathMode::NewRoad) {
+				Some(path) => path.tiles,
+				None => Vec::new(),
+			},
 		}
 	}
 }
 
+/// Applies tile interactions in order, returns the state from before of every tile that they could have changed.
+fn interact_tiles(tiles: &mut ndarray::Array2<tile::Tile>, tile_interactions: &[([u16; 2], TileInteraction)]) -> Vec<([u16; 2], tile::Tile)> {
+	let mut positions = BTreeSet::new();
+	let mut tiles_before = Vec::new();
+	for (pos, tile_interaction) in tile_interactions {
+		for affected_pos in tile_interaction.get_affected_tiles(tiles, *pos) {
+			if positions.insert(affected_pos) {
+				tiles_before.push((affected_pos, tiles[[affected_pos[0] as usize, affected_pos[1] as usize]].clone()));
+			}
+		}
+		tile_interaction.interact(tiles, *pos);
+	}
+	tiles_before
+}
+
+/// Gets what tile interactions would cost without changing the tiles, by trying them on a copy of only the part of the world they touch.
+pub fn get_cost(tiles: &ndarray::Array2<tile::Tile>, tile_interactions: &[([u16; 2], TileInteraction)]) -> i64 {
+	let positions: Vec<[u16; 2]> = tile_interactions.iter()
+		.flat_map(|(pos, tile_interaction)| std::iter::once(*pos).chain(tile_interaction.get_affected_tiles(tiles, *pos)))
+		.collect();
+	if positions.is_empty() {
+		return 0;
+	}
+	// Interactions also look at the tiles next to the ones they change, such as for road connections
+	let size = get_size(tiles);
+	let min = [0, 1].map(|axis| positions.iter().map(|pos| pos[axis]).min().unwrap_or(0).saturating_sub(1));
+	let max = [0, 1].map(|axis| positions.iter().map(|pos| pos[axis]).max().unwrap_or(0).saturating_add(1).min(size[axis] - 1));
+	let mut part = tiles.slice(ndarray::s![min[0] as usize..=max[0] as usize, min[1] as usize..=max[1] as usize]).to_owned();
+	let tile_interactions: Vec<([u16; 2], TileInteraction)> = tile_interactions.iter()
+		.map(|(pos, tile_interaction)| ([pos[0] - min[0], pos[1] - min[1]], *tile_interaction))
+		.collect();
+	let tiles_before = interact_tiles(&mut part, &tile_interactions);
+	economy::get_cost(&tiles_before, &part)
+}
+
+/// Gets the tiles in order from one tile to another, going diagonally until they share a row or column and then straight.
+pub fn get_leg_tiles(from: [u16; 2], to: [u16; 2]) -> Vec<[u16; 2]> {
+	let step = |from: u16, to: u16| match from.cmp(&to) {
+		std::cmp::Ordering::Less => from + 1,
+		std::cmp::Ordering::Equal => from,
+		std::cmp::Ordering::Greater => from - 1,
+	};
+	let mut out = vec![from];
+	let mut pos = from;
+	while pos != to {
+		pos = [step(pos[0], to[0]), step(pos[1], to[1])];
+		out.push(pos);
+	}
+	out
+}
+
+/// Gets the tiles in order along a line drag from the click start, which can be straight or at 45 degrees.
+fn get_line_tiles(mouse: &mouse::Mouse) -> Vec<[u16; 2]> {
+	get_leg_tiles(mouse.click_start, mouse.get_line_drag_end())
+}
+
+/// Gets which road quarters join each tile of a path to the tiles before and after it.
+fn get_path_road_masks(path: &[[u16; 2]]) -> Vec<([u16; 2], [bool; 8])> {
+	let mut out: Vec<([u16; 2], [bool; 8])> = path.iter().map(|pos| (*pos, [false; 8])).collect();
+	for index in 1..path.len() {
+		if let Some(direction) = direction::Direction8::from_neighbors(path[index - 1], path[index]) {
+			out[index - 1].1[direction.get_int_val() as usize] = true;
+			out[index].1[direction.get_opposite().get_int_val() as usize] = true;
+		}
+	}
+	out
+}
+
+/// Gets the tiles along a road line drag from the click start to the mouse, along with which road quarters join each tile to the next.
+fn get_road_line(mouse: &mouse::Mouse) -> Vec<([u16; 2], [bool; 8])> {
+	get_path_road_masks(&get_line_tiles(mouse))
+}
+
+/// Gets which tiles of a road path should be bridges and along which axis, which are runs of water or swamp that are short enough,
+/// straight and have land that can take a road at both ends.
+fn get_bridge_axes(tiles: &ndarray::Array2<tile::Tile>, line: &[([u16; 2], [bool; 8])]) -> Vec<Option<direction::Direction2>> {
+	let get_tile = |index: usize| &tiles[[line[index].0[0] as usize, line[index].0[1] as usize]];
+	let get_axis = |index: usize| direction::Direction8::from_neighbors(line[index - 1].0, line[index].0).and_then(direction::Direction8::get_axis);
+	let mut out = vec![None; line.len()];
+	let mut index = 0;
+	while index < line.len() {
+		if !get_tile(index).ground.is_water() {
+			index += 1;
+			continue;
+		}
+		let start = index;
+		while index < line.len() && get_tile(index).ground.is_water() {
+			index += 1;
+		}
+		// The water runs from start to just before index, the land at each end is just outside that
+		if start == 0 || index == line.len() || index - start > MAX_BRIDGE_LENGTH {
+			continue;
+		}
+		let axis = match get_axis(start) {
+			Some(valid) => valid,
+			None => continue,
+		};
+		let is_valid = (start + 1..=index).all(|step_index| get_axis(step_index) == Some(axis)) &&
+			can_build_road_on(get_tile(start - 1)) && can_build_road_on(get_tile(index)) &&
+			(start..index).all(|water_index| can_build_bridge_on(get_tile(water_index), axis));
+		if is_valid {
+			out[start..index].fill(Some(axis));
+		}
+	}
+	out
+}
+
+/// Gets if a cover could be built on a tile, which needs it to be empty and not on fire.
+fn can_build_cover_on(tile: &tile::Tile, cover: tile::Cover) -> bool {
+	tile.cover == tile::Cover::None && tile.fire == 0 && cover.can_go_on_ground(tile.ground)
+}
+
+/// Gets if a road could be built on a tile, either on empty ground or joining a road that is already there.
+pub fn can_build_road_on(tile: &tile::Tile) -> bool {
+	matches!(tile.cover, tile::Cover::None | tile::Cover::Road(_)) && tile.fire == 0 && tile::Cover::Road([tile::Road::None; 8]).can_go_on_ground(tile.ground)
+}
+
+fn can_build_bridge_on(tile: &tile::Tile, axis: direction::Direction2) -> bool {
+	let is_free = match tile.cover {
+		tile::Cover::None => true,
+		tile::Cover::Bridge(bridge_axis, _) => bridge_axis == axis,
+		_ => false,
+	};
+	is_free && tile.fire == 0 && tile::Cover::Bridge(axis, tile::Road::None).can_go_on_ground(tile.ground)
+}
+
+/// Gets if a tile has any of the road quarters that are set.
+fn can_erase_road(tile: &tile::Tile, road_mask: [bool; 8]) -> bool {
+	match tile.cover.get_road_quarters() {
+		Some(road_quarters) => road_quarters.iter().zip(road_mask).any(|(road_quarter, is_set)| is_set && *road_quarter != tile::Road::None),
+		None => false,
+	}
+}
+
+/// Gets if a tile's ground can be replaced, which is blocked under bridges.
+fn can_replace_ground(tile: &tile::Tile, ground: tile::Ground) -> bool {
+	!matches!(tile.cover, tile::Cover::Bridge(..)) && tile.cover.can_go_on_ground(ground)
+}
+
+/// Gets the tiles inside an ellipse, or only the ones on its edge if is_outline is set.
+fn get_ellipse_tiles(center: [f32; 2], radii: [f32; 2], size: [u16; 2], is_outline: bool) -> Vec<[u16; 2]> {
+	let is_inside = |x: i32, y: i32| ((x as f32 - center[0]) / radii[0]).powi(2) + ((y as f32 - center[1]) / radii[1]).powi(2) <= 1.;
+	let x_range = (center[0] - radii[0]).floor().max(0.) as i32..=(center[0] + radii[0]).ceil().min(size[0] as f32 - 1.) as i32;
+	let y_range = (center[1] - radii[1]).floor().max(0.) as i32..=(center[1] + radii[1]).ceil().min(size[1] as f32 - 1.) as i32;
+	let mut out = Vec::new();
+	for y in y_range {
+		for x in x_range.clone() {
+			if !is_inside(x, y) {
+				continue;
+			}
+			// Tiles on the edge have a straight neighbor outside the ellipse
+			if is_outline && [[0, -1], [1, 0], [0, 1], [-1, 0]].iter().all(|[offset_x, offset_y]| is_inside(x + offset_x, y + offset_y)) {
+				continue;
+			}
+			out.push([x as u16, y as u16]);
+		}
+	}
+	out
+}
+
+/// Gets the tiles inside a circle around a tile.
+fn get_brush_tiles(pos: [u16; 2], radius: u16, size: [u16; 2]) -> Vec<[u16; 2]> {
+	// Half a tile more than the radius so the edges are not single tiles sticking out
+	let radius = radius as f32 + 0.5;
+	get_ellipse_tiles([pos[0] as f32, pos[1] as f32], [radius, radius], size, false)
+}
+
+/// Gets the tiles of an ellipse that fits in the rectangle between two corner tiles.
+fn get_dragged_ellipse_tiles(corner: [u16; 2], opposite_corner: [u16; 2], size: [u16; 2], is_outline: bool) -> Vec<[u16; 2]> {
+	let center = [(corner[0] as f32 + opposite_corner[0] as f32) / 2., (corner[1] as f32 + opposite_corner[1] as f32) / 2.];
+	let radii = [corner[0].abs_diff(opposite_corner[0]) as f32 / 2. + 0.5, corner[1].abs_diff(opposite_corner[1]) as f32 / 2. + 0.5];
+	get_ellipse_tiles(center, radii, size, is_outline)
+}
+
 #[derive(Copy, Clone)]
 pub enum InteractionShape {
 	Dot(TileInteraction),
 	Rectangle(TileInteraction),
-	RoadLine(tile::Road),
+	/// A circle with a radius around the mouse that keeps painting while the mouse is dragged.
+	Brush(TileInteraction, u16),
+	/// A filled ellipse that fits in the dragged rectangle.
+	Ellipse(TileInteraction),
+	/// The edge of an ellipse that fits in the dragged rectangle.
+	EllipseOutline(TileInteraction),
+	RoadLine(tile::Road, RoadDrag),
+	/// Upgrades or downgrades the roads along a line.
+	ChangeRoadLine(RoadChange),
+	/// Removes the roads along a line, on both sides of each edge between tiles.
+	EraseRoadLine,
 }
 
 impl InteractionShape {
-	pub fn interact(&self, tiles: &mut ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse) {
+	/// Turns what the tool builds a quarter turn clockwise, or swaps which way a corner road goes first.
+	/// Tools that build things without a facing are not changed.
+	pub fn rotate(&mut self) {
+		match self {
+			Self::Dot(tile_interaction) | Self::Rectangle(tile_interaction) | Self::Brush(tile_interaction, _) |
+			Self::Ellipse(tile_interaction) | Self::EllipseOutline(tile_interaction) => tile_interaction.rotate(),
+			Self::RoadLine(_, RoadDrag::Corner { is_north_south_first }) => *is_north_south_first = !*is_north_south_first,
+			_ => {},
+		}
+	}
+
+	/// Gets the next shape for painting an area with the same tile interaction, None for shapes that do not paint areas.
+	pub fn get_next_area_shape(self) -> Option<Self> {
+		Some(match self {
+			Self::Rectangle(tile_interaction) => Self::Brush(tile_interaction, DEFAULT_BRUSH_RADIUS),
+			Self::Brush(tile_interaction, _) => Self::Ellipse(tile_interaction),
+			Self::Ellipse(tile_interaction) => Self::EllipseOutline(tile_interaction),
+			Self::EllipseOutline(tile_interaction) => Self::Rectangle(tile_interaction),
+			_ => return None,
+		})
+	}
+
+	/// Makes a brush bigger or smaller, returns the new radius or None if this is not a brush.
+	pub fn change_brush_radius(&mut self, change: i16) -> Option<u16> {
+		match self {
+			Self::Brush(_, radius) => {
+				*radius = radius.saturating_add_signed(change).min(MAX_BRUSH_RADIUS);
+				Some(*radius)
+			}
+			_ => None,
+		}
+	}
+
+	/// Gets if the interaction keeps being applied while the mouse is dragged rather than once when it is released.
+	pub fn is_continuous(&self) -> bool {
+		matches!(self, Self::Brush(..))
+	}
+
+	pub fn get_name(&self) -> &'static str {
 		match self {
-			Self::Dot(tile_interaction) => {
-				tile_interaction.interact(&mut tiles[[mouse.pos[0] as usize, mouse.pos[1] as usize]]);
+			Self::Dot(_) => "dot",
+			Self::Rectangle(_) => "rectangle",
+			Self::Brush(..) => "brush",
+			Self::Ellipse(_) => "ellipse",
+			Self::EllipseOutline(_) => "ellipse outline",
+			Self::RoadLine(..) | Self::ChangeRoadLine(_) | Self::EraseRoadLine => "road line",
+		}
+	}
+
+	/// Gets the tile interactions that the shape is made of and where each of them is used, in the order they are applied.
+	pub fn get_tile_interactions(&self, tiles: &ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse) -> Vec<([u16; 2], TileInteraction)> {
+		match self {
+			Self::Brush(tile_interaction, radius) => get_brush_tiles(mouse.pos, *radius, get_size(tiles)).into_iter()
+				.map(|pos| (pos, *tile_interaction))
+				.collect(),
+			Self::Ellipse(tile_interaction) | Self::EllipseOutline(tile_interaction) => {
+				get_dragged_ellipse_tiles(mouse.click_start, mouse.pos, get_size(tiles), matches!(self, Self::EllipseOutline(_))).into_iter()
+					.map(|pos| (pos, *tile_interaction))
+					.collect()
 			}
+			Self::Dot(tile_interaction) => vec![(mouse.pos, *tile_interaction)],
 			Self::Rectangle(tile_interaction) => {
+				let mut out = Vec::new();
 				for y in mouse.click_start[1].min(mouse.pos[1])..=mouse.click_start[1].max(mouse.pos[1]) {
 					for x in mouse.click_start[0].min(mouse.pos[0])..=mouse.click_start[0].max(mouse.pos[0]) {
-						tile_interaction.interact(&mut tiles[[x as usize, y as usize]]);
+						out.push(([x, y], *tile_interaction));
 					}
 				}
+				out
 			}
-			Self::RoadLine(road) => {
-				match mouse.get_line_drag_direction() {
-					direction::Direction2::NorthSouth => {
-						let x = mouse.click_start[0];
-						let min = mouse.click_start[1].min(mouse.pos[1]);
-						let max = mouse.click_start[1].max(mouse.pos[1]);
-						if min == max {
-							return
-						}
-						for y in min..=max {
-							let mut road_quarters = [tile::Road::None; 4];
-							if y != min {
-								road_quarters[0] = *road;
-							}
-							if y != max {
-								road_quarters[2] = *road;
-							}
-							let tile_interaction = TileInteraction::BuildRoad(road_quarters);
-							tile_interaction.interact(&mut tiles[[x as usize, y as usize]]);
-						}
-					},
-					direction::Direction2::EastWest => {
-						let y = mouse.click_start[1];
-						let min = mouse.click_start[0].min(mouse.pos[0]);
-						let max = mouse.click_start[0].max(mouse.pos[0]);
-						if min == max {
-							return
-						}
-						for x in min..=max {
-							let mut road_quarters = [tile::Road::None; 4];
-							if x != min {
-								road_quarters[3] = *road;
-							}
-							if x != max {
-								road_quarters[1] = *road;
-							}
-							let tile_interaction = TileInteraction::BuildRoad(road_quarters);
-							tile_interaction.interact(&mut tiles[[x as usize, y as usize]]);
-						}
-					},
+			Self::RoadLine(road, drag) => {
+				let line = get_path_road_masks(&drag.get_tiles(tiles, mouse));
+				if line.len() < 2 {
+					return Vec::new();
 				}
+				let bridge_axes = get_bridge_axes(tiles, &line);
+				line.into_iter().zip(bridge_axes).map(|((pos, road_mask), bridge_axis)| {
+					let tile_interaction = match bridge_axis {
+						Some(axis) => TileInteraction::BuildBridge(axis, *road),
+						None => TileInteraction::BuildRoad(road_mask.map(|is_set| match is_set {
+							true => *road,
+							false => tile::Road::None,
+						})),
+					};
+					(pos, tile_interaction)
+				}).collect()
 			}
+			Self::ChangeRoadLine(change) => {
+				let line = get_road_line(mouse);
+				if line.len() < 2 {
+					return Vec::new();
+				}
+				line.into_iter().map(|(pos, road_mask)| (pos, TileInteraction::ChangeRoad(*change, road_mask))).collect()
+			}
+			Self::EraseRoadLine => get_road_line(mouse).into_iter().map(|(pos, road_mask)| (pos, TileInteraction::EraseRoad(road_mask))).collect(),
 		}
 	}
 
-	pub fn generate_select_tris(&self, tiles: &ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse) -> Vec<vertex::Vertex> {
+	/// Applies the interaction, returns the state from before of every tile that it could have changed.
+	pub fn interact(&self, tiles: &mut ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse) -> Vec<([u16; 2], tile::Tile)> {
+		interact_tiles(tiles, &self.get_tile_interactions(tiles, mouse))
+	}
+
+	/// Gets if the cost of the interaction can be shown, drags are only known while the mouse is held.
+	pub fn has_cost_preview(&self, mouse: &mouse::Mouse) -> bool {
+		mouse.is_left_clicking || matches!(self, Self::Dot(_) | Self::Brush(..))
+	}
+
+	/// Generates the preview of what the interaction will do.
+	/// Road lines are drawn from tile_interactions, which are what get_tile_interactions gives while the mouse is held.
+	pub fn generate_select_tris(&self, tiles: &ndarray::Array2<tile::Tile>, mouse: &mouse::Mouse, tile_interactions: &[([u16; 2], TileInteraction)]) -> Vec<vertex::Vertex> {
 		match (self, mouse.is_left_clicking) {
 			(Self::Rectangle(interaction), true) => {
 				let mut tris: Vec<vertex::Vertex> = Vec::new();
 				for y in mouse.click_start[1].min(mouse.pos[1])..=mouse.click_start[1].max(mouse.pos[1]) {
 					for x in mouse.click_start[0].min(mouse.pos[0])..=mouse.click_start[0].max(mouse.pos[0]) {
-						tris.extend(interaction.generate_select_tris(&tiles[[x as usize, y as usize]], [x, y]));
+						tris.extend(interaction.generate_select_tris(tiles, [x, y]));
 					}
 				}
 				tris
 			}
-			(Self::Dot(interaction) | Self::Rectangle(interaction), _) => {
-				interaction.generate_select_tris(&tiles[[mouse.pos[0] as usize, mouse.pos[1] as usize]], mouse.pos).to_vec()
+			(Self::Brush(interaction, radius), _) => get_brush_tiles(mouse.pos, *radius, get_size(tiles)).into_iter()
+				.flat_map(|pos| interaction.generate_select_tris(tiles, pos))
+				.collect(),
+			(Self::Ellipse(interaction) | Self::EllipseOutline(interaction), true) => {
+				get_dragged_ellipse_tiles(mouse.click_start, mouse.pos, get_size(tiles), matches!(self, Self::EllipseOutline(_))).into_iter()
+					.flat_map(|pos| interaction.generate_select_tris(tiles, pos))
+					.collect()
+			}
+			(Self::Dot(interaction) | Self::Rectangle(interaction) | Self::Ellipse(interaction) | Self::EllipseOutline(interaction), _) => {
+				interaction.generate_select_tris(tiles, mouse.pos)
+			}
+			(Self::RoadLine(..), true) => {
+				// Mark the ends red if there is no route between them
+				if tile_interactions.is_empty() {
+					return [mouse.click_start, mouse.pos].into_iter().flat_map(|pos| texture::Texture::SelectUnbuildable.generate_tris(pos)).collect();
+				}
+				let mut tris: Vec<vertex::Vertex> = Vec::new();
+				for (pos, tile_interaction) in tile_interactions {
+					let interaction = match tile_interaction {
+						TileInteraction::BuildRoad(_) => TileInteraction::BuildRoad([tile::Road::None; 8]),
+						_ => *tile_interaction,
+					};
+					tris.extend(interaction.generate_select_tris(tiles, *pos));
+				}
+				tris
+			}
+			(Self::ChangeRoadLine(change), true) => {
+				let mut tris: Vec<vertex::Vertex> = Vec::new();
+				for (pos, road_mask) in get_road_line(mouse) {
+					tris.extend(TileInteraction::ChangeRoad(*change, road_mask).generate_select_tris(tiles, pos));
+				}
+				tris
 			}
-			(Self::RoadLine(_), true) => {
+			(Self::EraseRoadLine, true) => {
 				let mut tris: Vec<vertex::Vertex> = Vec::new();
-				let interaction = TileInteraction::BuildRoad([tile::Road::None; 4]);
-				match mouse.get_line_drag_direction() {
-					direction::Direction2::NorthSouth => {
-						let x = mouse.click_start[0];
-						for y in mouse.click_start[1].min(mouse.pos[1])..=mouse.click_start[1].max(mouse.pos[1]) {
-							tris.extend(interaction.generate_select_tris(&tiles[[x as usize, y as usize]], [x, y]));
-						}
-					},
-					direction::Direction2::EastWest => {
-						let y = mouse.click_start[1];
-						for x in mouse.click_start[0].min(mouse.pos[0])..=mouse.click_start[0].max(mouse.pos[0]) {
-							tris.extend(interaction.generate_select_tris(&tiles[[x as usize, y as usize]], [x, y]));
-						}
-					},
+				for (pos, road_mask) in get_road_line(mouse) {
+					tris.extend(TileInteraction::EraseRoad(road_mask).generate_select_tris(tiles, pos));
 				}
 				tris
 			}
@@ -1,7 +1,7 @@
 use glium::glutin::event;
 use glium::{self, uniforms, Blend};
 use glium::{glutin, glutin::{event_loop, window, dpi}, Surface};
-use std::io::Cursor;
+use std::{env, path, process, time};
 mod world;
 mod vertex;
 mod texture;
@@ -10,8 +10,56 @@ mod interaction;
 mod mouse;
 mod gui;
 mod direction;
+mod save;
+mod history;
+mod random;
+mod terrain;
+mod export;
+mod import;
+mod headless;
+mod simulation;
+mod forest;
+mod water;
+mod fire;
+mod road_network;
+mod pathfinding;
+mod vehicle;
+mod zoning;
+mod economy;
+mod world_event;
+
+const SAVE_PATH: &str = "world.gsim";
+const EXPORT_PATH: &str = "world.png";
+const IMPORT_PATH: &str = "import.png";
+const WORLD_SIZE: u16 = 256;
+// Amount of tiles added or removed by each resize keypress
+const RESIZE_STEP: i32 = 16;
+// Amount of interactions that can be undone
+const HISTORY_SIZE: usize = 100;
+
+/// Paints a brush at the mouse, adding what changed to the stroke.
+fn paint(world: &mut world::World, interaction: &interaction::InteractionShape, mouse: &mouse::Mouse, stroke: &mut Option<history::Action>) {
+	match world.interact(interaction, mouse) {
+		Ok(Some(action)) => match stroke {
+			Some(valid) => valid.merge(action),
+			None => *stroke = Some(action),
+		},
+		Ok(None) => {}
+		Err(error) => println!("{error}"),
+	}
+}
 
 fn main() {
+	// Run without a window when asked to
+	let args: Vec<String> = env::args().skip(1).collect();
+	if args.first().map(String::as_str) == Some("--headless") {
+		if let Err(error) = headless::run(&args[1..]) {
+			eprintln!("{error}");
+			process::exit(1);
+		}
+		return;
+	}
+
 	// Create window
 	let events_loop = event_loop::EventLoop::new();
 	let window_builder = window::WindowBuilder::new()
@@ -20,8 +68,7 @@ fn main() {
 	let display = glium::Display::new(window_builder, context_builder, &events_loop).unwrap();
 
 	// Create texture
-	let image = image::load(Cursor::new(&include_bytes!("textures.png")),
-						image::ImageFormat::Png).unwrap().to_rgba8();
+	let image = texture::load_atlas();
 	let image_dimensions = image.dimensions();
 	let image = glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions);
 	let texture = glium::texture::SrgbTexture2d::new(&display, image).unwrap();
@@ -50,12 +97,18 @@ fn main() {
 	let mut cursor_y = 0u16;
 	let mut window_width = 0u16;
 	let mut window_height = 0u16;
+	let mut modifiers = event::ModifiersState::empty();
 
 	// Structs
-	let mut world = world::World::new();
+	let mut world = world::World::new(WORLD_SIZE, WORLD_SIZE);
 	let mut interaction = interaction::InteractionShape::Rectangle(interaction::TileInteraction::ReplaceGround(tile::Ground::Bricks));
 	let mut mouse = mouse::Mouse{ pos: [0u16; 2], click_start: [0u16; 2], is_left_clicking: false, is_middle_clicking: false, is_right_clicking: false, gui_pos: [0; 2] };
-	let mut gui = gui::GUI::new();
+	let mut gui = gui::Gui::new();
+	let mut history = history::History::new(HISTORY_SIZE);
+	let mut clock = simulation::Clock::new();
+	let mut path_overlay = pathfinding::PathOverlay::new();
+	// Changes made so far by the brush stroke being dragged, undone together
+	let mut stroke: Option<history::Action> = None;
 
 	// Program loop
 	events_loop.run(move |event, _, control_flow| {
@@ -65,12 +118,13 @@ fn main() {
 				// On exit button press
 				event::WindowEvent::CloseRequested => *control_flow = glutin::event_loop::ControlFlow::Exit,
 				// On scroll wheel scrool
-				event::WindowEvent::MouseWheel { device_id: _, delta, phase: _, ..} => {
-					match delta {
-						event::MouseScrollDelta::LineDelta(_, y) => {
-							zoom_level = (zoom_level - (y as i8)).clamp(-4, 3);
+				event::WindowEvent::MouseWheel { device_id: _, delta: event::MouseScrollDelta::LineDelta(_, y), phase: _, ..} => {
+					// Ctrl changes the size of brushes instead of zooming
+					match modifiers.ctrl() && interaction.is_continuous() {
+						true => if let Some(radius) = interaction.change_brush_radius(y.signum() as i16) {
+							println!("Brush radius: {radius}");
 						},
-						_ => {}
+						false => zoom_level = (zoom_level - (y as i8)).clamp(-4, 3),
 					}
 				}
 				// On cursor move
@@ -87,14 +141,22 @@ fn main() {
 					let cursor_world_y = ((scroll_y / 16.) + (((cursor_y as i32) - ((window_height as i32) / 2)) as f32) / zoom / 16.) as i32;
 
 					let width_excess = window_width as i32 - (window_height as i32);
-					let cursor_gui_x = ((cursor_x as i32 - (width_excess as i32) / 2) as f32 * 16. / ((window_height as i32) as f32)).clamp(0., 16.) as u8;
+					let cursor_gui_x = ((cursor_x as i32 - width_excess / 2) as f32 * 16. / ((window_height as i32) as f32)).clamp(0., 16.) as u8;
 					let cursor_gui_y = (cursor_y as f32 * 16. / (window_height as f32)).clamp(0., 16.) as u8;
 
+					let last_pos = mouse.pos;
 					mouse.set_pos([cursor_world_x, cursor_world_y], [cursor_gui_x, cursor_gui_y], &world);
+					// Brushes paint every tile they are dragged over, including ones the cursor skipped between events
+					if mouse.is_left_clicking && !gui.is_open && interaction.is_continuous() && mouse.pos != last_pos {
+						for pos in interaction::get_leg_tiles(last_pos, mouse.pos).into_iter().skip(1) {
+							mouse.pos = pos;
+							paint(&mut world, &interaction, &mouse, &mut stroke);
+						}
+					}
 					// If right clicking then pan camera
 					if mouse.is_right_clicking {
-						scroll_x = (scroll_x - (delta_x as f32) / zoom).clamp(0., 4096.);
-						scroll_y = (scroll_y - (delta_y as f32) / zoom).clamp(0., 4096.);
+						scroll_x = (scroll_x - (delta_x as f32) / zoom).clamp(0., world.get_width() as f32 * 16.);
+						scroll_y = (scroll_y - (delta_y as f32) / zoom).clamp(0., world.get_height() as f32 * 16.);
 					}
 				}
 				// Window resize
@@ -118,15 +180,35 @@ fn main() {
 					if matches!(button, event::MouseButton::Left) {
 						match state {
 							event::ElementState::Released => {
-								match gui.is_open {
-									true => gui.click(&mouse, &mut interaction),
-									false => world.interact(&interaction, &mouse),
+								// A brush has already painted, the whole stroke becomes one action
+								if let Some(action) = stroke.take() {
+									history.push(action);
+									path_overlay.update(&world.tiles);
+								}
+								match (gui.is_open, interaction.is_continuous()) {
+									(true, _) => gui.click(&mouse, &mut interaction),
+									(false, true) => {}
+									(false, false) => match world.interact(&interaction, &mouse) {
+										Ok(Some(action)) => {
+											history.push(action);
+											path_overlay.update(&world.tiles);
+										}
+										Ok(None) => {}
+										Err(error) => println!("{error}"),
+									},
+								}
+							},
+        					event::ElementState::Pressed => {
+								mouse.set_click_start();
+								if !gui.is_open && interaction.is_continuous() {
+									paint(&mut world, &interaction, &mouse, &mut stroke);
 								}
 							},
-        					event::ElementState::Pressed => mouse.set_click_start(),
 						}
 					}
 				}
+				// Modifier keys
+				event::WindowEvent::ModifiersChanged(state) => modifiers = state,
 				// Keyboard keypress
 				event::WindowEvent::KeyboardInput { device_id: _, input, is_synthetic: _ } => {
 					let keycode = match input.virtual_keycode {
@@ -137,13 +219,150 @@ fn main() {
 					if matches!(input.state, event::ElementState::Released) {
 						match keycode {
 							event::VirtualKeyCode::F11 => {
-								let is_fullscreen = !matches!(display.gl_window().window().fullscreen(), None);
+								let is_fullscreen = display.gl_window().window().fullscreen().is_some();
 								display.gl_window().window().set_fullscreen(match is_fullscreen {
 									true => None,
 									false => Some(window::Fullscreen::Borderless(None))
 								});
 							}
 							event::VirtualKeyCode::B => gui.is_open = !gui.is_open,
+							event::VirtualKeyCode::R => interaction.rotate(),
+							event::VirtualKeyCode::Tab => if let Some(next) = interaction.get_next_area_shape() {
+								interaction = next;
+								println!("Shape: {}", interaction.get_name());
+							},
+							event::VirtualKeyCode::LBracket | event::VirtualKeyCode::RBracket => {
+								let change = match keycode {
+									event::VirtualKeyCode::LBracket => -1,
+									_ => 1,
+								};
+								if let Some(radius) = interaction.change_brush_radius(change) {
+									println!("Brush radius: {radius}");
+								}
+							}
+							event::VirtualKeyCode::L => if let interaction::InteractionShape::RoadLine(_, drag) = &mut interaction {
+								*drag = drag.get_next();
+								println!("Road drag: {}", drag.get_name());
+							},
+							// Debug path between two tiles
+							event::VirtualKeyCode::F5 => {
+								path_overlay.start = Some(mouse.pos);
+								path_overlay.update(&world.tiles);
+							}
+							event::VirtualKeyCode::F6 => {
+								path_overlay.goal = Some(mouse.pos);
+								path_overlay.update(&world.tiles);
+							}
+							event::VirtualKeyCode::F7 => {
+								path_overlay.mode = match path_overlay.mode {
+									pathfinding::PathMode::RoadOnly => pathfinding::PathMode::OffRoad,
+									pathfinding::PathMode::OffRoad => pathfinding::PathMode::NewRoad,
+									pathfinding::PathMode::NewRoad => pathfinding::PathMode::RoadOnly,
+								};
+								path_overlay.update(&world.tiles);
+							}
+							event::VirtualKeyCode::F8 => path_overlay = pathfinding::PathOverlay::new(),
+							// Simulation speed
+							event::VirtualKeyCode::Space => clock.is_paused = !clock.is_paused,
+							event::VirtualKeyCode::Key1 | event::VirtualKeyCode::Key2 | event::VirtualKeyCode::Key3 => {
+								clock.speed = match keycode {
+									event::VirtualKeyCode::Key1 => simulation::SimulationSpeed::Normal,
+									event::VirtualKeyCode::Key2 => simulation::SimulationSpeed::Double,
+									_ => simulation::SimulationSpeed::Quadruple,
+								};
+								clock.is_paused = false;
+							}
+							event::VirtualKeyCode::S if modifiers.ctrl() => {
+								if let Err(error) = world.save(path::Path::new(SAVE_PATH)) {
+									eprintln!("Failed to save world to {SAVE_PATH}: {error}");
+								}
+							}
+							event::VirtualKeyCode::O if modifiers.ctrl() => {
+								match world::World::load(path::Path::new(SAVE_PATH)) {
+									Ok(loaded) => {
+										world = loaded;
+										history.clear();
+										path_overlay.clear();
+									}
+									Err(error) => eprintln!("Failed to load world from {SAVE_PATH}: {error}"),
+								}
+								mouse.clamp_to_world(&world);
+								scroll_x = scroll_x.min(world.get_width() as f32 * 16.);
+								scroll_y = scroll_y.min(world.get_height() as f32 * 16.);
+							}
+							// Export one pixel per tile, or the full sprites when shift is held
+							event::VirtualKeyCode::E if modifiers.ctrl() => {
+								let mode = match modifiers.shift() {
+									true => export::ExportMode::Full,
+									false => export::ExportMode::Minimap,
+								};
+								let result = export::export(&world, mode).map_err(|error| error.to_string())
+									.and_then(|image| image.save(EXPORT_PATH).map_err(|error| error.to_string()));
+								if let Err(error) = result {
+									eprintln!("Failed to export world to {EXPORT_PATH}: {error}");
+								}
+							}
+							event::VirtualKeyCode::I if modifiers.ctrl() => {
+								match import::import(path::Path::new(IMPORT_PATH), &import::get_default_palette()) {
+									Ok(import) => {
+										for [x, y] in import.skipped_covers {
+											println!("Skipped cover at {x}, {y} that cannot go on its ground or has nothing to join");
+										}
+										world = import.world;
+										history.clear();
+										path_overlay.clear();
+									}
+									Err(error) => eprintln!("Failed to import world from {IMPORT_PATH}: {error}"),
+								}
+								mouse.clamp_to_world(&world);
+								scroll_x = scroll_x.min(world.get_width() as f32 * 16.);
+								scroll_y = scroll_y.min(world.get_height() as f32 * 16.);
+							}
+							// Generate a new world from a seed based on the current time
+							event::VirtualKeyCode::N if modifiers.ctrl() => {
+								let seed = time::SystemTime::now().duration_since(time::UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64);
+								println!("Generating world with seed {seed}");
+								let parameters = terrain::TerrainParameters { seed, ..Default::default() };
+								world = terrain::generate(world.get_width(), world.get_height(), &parameters);
+								history.clear();
+								path_overlay.clear();
+							}
+							event::VirtualKeyCode::Z if modifiers.ctrl() => history.undo(&mut world),
+							event::VirtualKeyCode::Y if modifiers.ctrl() => if let Err(error) = history.redo(&mut world) {
+								println!("{error}");
+							},
+							// Toggle sandbox mode where everything is free
+							event::VirtualKeyCode::M => {
+								world.is_sandbox = !world.is_sandbox;
+								match world.is_sandbox {
+									true => println!("Sandbox mode on"),
+									false => println!("Sandbox mode off"),
+								}
+							}
+							// Grow the world on the side of the arrow key or on all sides for page up, or crop when shift is held
+							event::VirtualKeyCode::Up | event::VirtualKeyCode::Down | event::VirtualKeyCode::Left | event::VirtualKeyCode::Right |
+							event::VirtualKeyCode::PageUp if modifiers.ctrl() => {
+								let step = match modifiers.shift() {
+									true => -RESIZE_STEP,
+									false => RESIZE_STEP,
+								};
+								let width = world.get_width() as i32;
+								let height = world.get_height() as i32;
+								let (width, height, anchor) = match keycode {
+									event::VirtualKeyCode::Up => (width, height + step, world::ResizeAnchor::South),
+									event::VirtualKeyCode::Down => (width, height + step, world::ResizeAnchor::North),
+									event::VirtualKeyCode::Left => (width + step, height, world::ResizeAnchor::East),
+									event::VirtualKeyCode::Right => (width + step, height, world::ResizeAnchor::West),
+									_ => (width + step * 2, height + step * 2, world::ResizeAnchor::Center),
+								};
+								let max_size = world::MAX_SIZE as i32;
+								world.resize(width.clamp(1, max_size) as u16, height.clamp(1, max_size) as u16, anchor);
+								history.clear();
+								path_overlay.clear();
+								mouse.clamp_to_world(&world);
+								scroll_x = scroll_x.min(world.get_width() as f32 * 16.);
+								scroll_y = scroll_y.min(world.get_height() as f32 * 16.);
+							}
 							_ => {}
 						}
 					}
@@ -153,12 +372,20 @@ fn main() {
 
 			// Draw
 			glutin::event::Event::MainEventsCleared => {
+				// Run the simulation ticks that are due
+				for _ in 0..clock.update() {
+					for event in world.tick() {
+						println!("{event}");
+					}
+				}
+
 				// Get frame for drawing on
 				let mut frame = display.draw();
 				frame.clear_color(0.2, 0.8, 1., 0.);
 
 				// Get tris for each tile
-				let world_tris = world.render(&interaction, &mouse, gui.is_open);
+				let mut world_tris = world.render(&interaction, &mouse, gui.is_open, clock.get_tick_fraction());
+				world_tris.extend(path_overlay.render());
 
 				// Draw world tris
 				let world_vertex_buffer = glium::vertex::VertexBuffer::new(&display, &world_tris).unwrap();
@@ -174,10 +401,14 @@ fn main() {
 					],
 					texture_sampler: uniforms::Sampler(&texture, behavior),
 				};
-				frame.draw(&world_vertex_buffer, &indices, &program, &uniforms, &draw_parameters).unwrap();
+				frame.draw(&world_vertex_buffer, indices, &program, &uniforms, &draw_parameters).unwrap();
 
 				// Get GUI tris
-				let gui_tris = gui.render();
+				let money = match world.is_sandbox {
+					true => None,
+					false => Some(world.money),
+				};
+				let gui_tris = gui.render(money);
 
 				// Draw GUI tris
 				let gui_vertex_buffer = glium::vertex::VertexBuffer::new(&display, &gui_tris).unwrap();
@@ -190,7 +421,7 @@ fn main() {
 					],
 					texture_sampler: uniforms::Sampler(&texture, behavior),
 				};
-				frame.draw(&gui_vertex_buffer, &indices, &program, &gui_uniforms, &draw_parameters).unwrap();
+				frame.draw(&gui_vertex_buffer, indices, &program, &gui_uniforms, &draw_parameters).unwrap();
 
 				frame.finish().unwrap();
 			}
@@ -1,5 +1,4 @@
 use super::world;
-use super::direction;
 
 pub struct Mouse {
 	pub pos: [u16; 2],
@@ -21,14 +20,34 @@ impl Mouse {
 		self.gui_pos = gui_pos;
 	}
 
+	/// Moves the mouse back inside the world after the world has changed size.
+	pub fn clamp_to_world(&mut self, world: &world::World) {
+		for pos in [&mut self.pos, &mut self.click_start] {
+			pos[0] = pos[0].min(world.get_width() - 1);
+			pos[1] = pos[1].min(world.get_height() - 1);
+		}
+	}
+
 	pub fn set_click_start(&mut self) {
 		self.click_start = self.pos;
 	}
 
-	pub fn get_line_drag_direction(&self) -> direction::Direction2 {
-		match self.pos[0].abs_diff(self.click_start[0]) > self.pos[1].abs_diff(self.click_start[1]) {
-			false => direction::Direction2::NorthSouth,
-			true => direction::Direction2::EastWest,
+	/// Gets where a line drag from the click start ends, locked to the nearest straight or 45 degree line.
+	pub fn get_line_drag_end(&self) -> [u16; 2] {
+		let distance_x = self.pos[0].abs_diff(self.click_start[0]);
+		let distance_y = self.pos[1].abs_diff(self.click_start[1]);
+		// Diagonal when the mouse is closer to 45 degrees than to either axis
+		if distance_x.min(distance_y) * 2 > distance_x.max(distance_y) {
+			let distance = distance_x.min(distance_y);
+			let move_towards = |from: u16, to: u16| match to >= from {
+				true => from + distance,
+				false => from - distance,
+			};
+			return [move_towards(self.click_start[0], self.pos[0]), move_towards(self.click_start[1], self.pos[1])];
+		}
+		match distance_x > distance_y {
+			false => [self.click_start[0], self.pos[1]],
+			true => [self.pos[0], self.click_start[1]],
 		}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+// A* search for the cheapest route between two tiles
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::direction;
+use super::interaction;
+use super::road_network;
+use super::texture;
+use super::tile;
+use super::vertex;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum PathMode {
+	/// Only follow road quarters that link neighboring tiles.
+	RoadOnly,
+	/// Cross any passable ground, paying its movement cost.
+	OffRoad,
+	/// Cross tiles that a road could be built on, preferring roads that are already there.
+	NewRoad,
+}
+
+/// Cost of moving one tile along an existing road when finding where to build a new one.
+const EXISTING_ROAD_COST: u32 = 5;
+
+impl PathMode {
+	/// Gets the cheapest cost of moving one tile, used to estimate the remaining cost.
+	fn get_min_cost(self) -> u32 {
+		match self {
+			PathMode::RoadOnly => tile::Road::Highway.get_movement_cost(),
+			PathMode::OffRoad => tile::Ground::Bricks.get_movement_cost().unwrap(),
+			PathMode::NewRoad => EXISTING_ROAD_COST,
+		}
+	}
+
+	/// Gets the fewest moves between two tiles, roads can link diagonally so a move can change both coordinates.
+	fn get_min_steps(self, from: [u16; 2], to: [u16; 2]) -> u32 {
+		let [distance_x, distance_y] = [from[0].abs_diff(to[0]) as u32, from[1].abs_diff(to[1]) as u32];
+		match self {
+			PathMode::RoadOnly => distance_x.max(distance_y),
+			PathMode::OffRoad | PathMode::NewRoad => distance_x + distance_y,
+		}
+	}
+
+	pub fn get_name(self) -> &'static str {
+		match self {
+			PathMode::RoadOnly => "road only",
+			PathMode::OffRoad => "off road",
+			PathMode::NewRoad => "new road",
+		}
+	}
+}
+
+pub struct Path {
+	/// Positions of every tile along the path, including the start and goal.
+	pub tiles: Vec<[u16; 2]>,
+	pub cost: u32,
+}
+
+/// Gets the tiles that can be moved to from a tile along with the cost of moving there.
+fn get_neighbors(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], mode: PathMode) -> Vec<([u16; 2], u32)> {
+	let size = [tiles.dim().0 as u16, tiles.dim().1 as u16];
+	match mode {
+		PathMode::RoadOnly => road_network::get_connections(tiles, pos).into_iter()
+			.map(|(direction, road, _)| (direction.offset_pos(pos, size).unwrap(), road.get_link_movement_cost(direction)))
+			.collect(),
+		PathMode::OffRoad => (0..4)
+			.filter_map(|direction| direction::Direction4::new(direction).offset_pos(pos, size))
+			.filter_map(|neighbor_pos| Some((neighbor_pos, tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]].ground.get_movement_cost()?)))
+			.collect(),
+		PathMode::NewRoad => (0..4)
+			.filter_map(|direction| direction::Direction4::new(direction).offset_pos(pos, size))
+			.filter_map(|neighbor_pos| {
+				let neighbor = &tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]];
+				if !interaction::can_build_road_on(neighbor) {
+					return None;
+				}
+				match neighbor.cover {
+					tile::Cover::Road(_) => Some((neighbor_pos, EXISTING_ROAD_COST)),
+					_ => Some((neighbor_pos, neighbor.ground.get_movement_cost()?)),
+				}
+			})
+			.collect(),
+	}
+}
+
+/// Finds the cheapest path from the start to the goal, None if the goal cannot be reached or either is outside the world.
+pub fn find_path(tiles: &ndarray::Array2<tile::Tile>, start: [u16; 2], goal: [u16; 2], mode: PathMode) -> Option<Path> {
+	let size = tiles.dim();
+	if [start, goal].iter().any(|pos| pos[0] as usize >= size.0 || pos[1] as usize >= size.1) {
+		return None;
+	}
+	let estimate = |pos: [u16; 2]| mode.get_min_steps(pos, goal) * mode.get_min_cost();
+	let mut costs = ndarray::Array2::from_elem(tiles.dim(), u32::MAX);
+	let mut came_from: ndarray::Array2<Option<[u16; 2]>> = ndarray::Array2::from_elem(tiles.dim(), None);
+	let mut open = BinaryHeap::new();
+	costs[[start[0] as usize, start[1] as usize]] = 0;
+	open.push(Reverse((estimate(start), 0, start)));
+
+	while let Some(Reverse((_, cost, pos))) = open.pop() {
+		if pos == goal {
+			let mut path_tiles = vec![goal];
+			while let Some(previous) = came_from[[path_tiles[path_tiles.len() - 1][0] as usize, path_tiles[path_tiles.len() - 1][1] as usize]] {
+				path_tiles.push(previous);
+			}
+			path_tiles.reverse();
+			return Some(Path { tiles: path_tiles, cost });
+		}
+		// Skip outdated entries for tiles that have since been reached more cheaply
+		if cost > costs[[pos[0] as usize, pos[1] as usize]] {
+			continue;
+		}
+		for (neighbor_pos, step_cost) in get_neighbors(tiles, pos, mode) {
+			let neighbor_cost = cost + step_cost;
+			let neighbor_index = [neighbor_pos[0] as usize, neighbor_pos[1] as usize];
+			if neighbor_cost < costs[neighbor_index] {
+				costs[neighbor_index] = neighbor_cost;
+				came_from[neighbor_index] = Some(pos);
+				open.push(Reverse((neighbor_cost + estimate(neighbor_pos), neighbor_cost, neighbor_pos)));
+			}
+		}
+	}
+	None
+}
+
+/// Debug view of the path between two chosen tiles.
+pub struct PathOverlay {
+	pub start: Option<[u16; 2]>,
+	pub goal: Option<[u16; 2]>,
+	pub mode: PathMode,
+	path: Option<Path>,
+}
+
+impl PathOverlay {
+	pub fn new() -> Self {
+		Self { start: None, goal: None, mode: PathMode::RoadOnly, path: None }
+	}
+
+	/// Removes the start, goal and path but keeps the mode, for when the world they were on is replaced or resized.
+	pub fn clear(&mut self) {
+		self.start = None;
+		self.goal = None;
+		self.path = None;
+	}
+
+	/// Finds the path again after the start, goal, mode or tiles have changed.
+	pub fn update(&mut self, tiles: &ndarray::Array2<tile::Tile>) {
+		self.path = match (self.start, self.goal) {
+			(Some(start), Some(goal)) => find_path(tiles, start, goal, self.mode),
+			_ => None,
+		};
+		if let (Some([start_x, start_y]), Some([goal_x, goal_y])) = (self.start, self.goal) {
+			match &self.path {
+				Some(path) => println!("Path ({}) from {start_x}, {start_y} to {goal_x}, {goal_y} costs {}", self.mode.get_name(), path.cost),
+				None => println!("No path ({}) from {start_x}, {start_y} to {goal_x}, {goal_y}", self.mode.get_name()),
+			}
+		}
+	}
+
+	pub fn render(&self) -> Vec<vertex::Vertex> {
+		let mut tris = Vec::new();
+		if let Some(path) = &self.path {
+			for pos in &path.tiles {
+				tris.extend(texture::Texture::SelectBuildable.generate_tris(*pos));
+			}
+		}
+		// Mark the ends red if there is no path between them
+		let end_texture = match self.path {
+			Some(_) => texture::Texture::Select,
+			None => texture::Texture::SelectUnbuildable,
+		};
+		for pos in [self.start, self.goal].into_iter().flatten() {
+			tris.extend(end_texture.generate_tris(pos));
+		}
+		tris
+	}
+}
@@ -0,0 +1,24 @@
+// Deterministic randomness so that the same seed always gives the same world
+
+/// Mixes a value the same way as the SplitMix64 generator does.
+fn mix(mut value: u64) -> u64 {
+	value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+	value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	value ^ (value >> 31)
+}
+
+/// Hashes a seed and some values into a random looking number, the same inputs always give the same output.
+pub fn hash(seed: u64, values: &[u64]) -> u64 {
+	values.iter().fold(mix(seed), |state, value| mix(state ^ value))
+}
+
+/// Like hash but gives a number from 0 up to but not including 1.
+pub fn hash_unit(seed: u64, values: &[u64]) -> f32 {
+	(hash(seed, values) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Hashes like hash and returns true with the given chance, which can be smaller than hash_unit can resolve.
+pub fn hash_chance(seed: u64, values: &[u64], chance: f32) -> bool {
+	hash(seed, values) < (chance as f64 * u64::MAX as f64) as u64
+}
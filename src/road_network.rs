@@ -0,0 +1,334 @@
+// Graph of the road network where junctions and dead ends are nodes and the roads between them are edges
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::direction;
+use super::tile;
+
+pub type EdgeId = usize;
+
+/// A junction, dead end or change of road type.
+pub struct Node {
+	pub edges: Vec<EdgeId>,
+}
+
+/// A run of road between two nodes.
+pub struct Edge {
+	/// Positions of the tiles along the edge, starting and ending at a node.
+	pub tiles: Vec<[u16; 2]>,
+	/// Direction that the edge leaves its start and end nodes in.
+	directions: [direction::Direction8; 2],
+	pub road: tile::Road,
+}
+
+impl Edge {
+	/// Gets the positions of the start and end nodes, which are the same for loops.
+	pub fn get_ends(&self) -> [[u16; 2]; 2] {
+		[self.tiles[0], self.tiles[self.tiles.len() - 1]]
+	}
+
+	/// Gets the length of the edge in tiles.
+	pub fn get_length(&self) -> u32 {
+		self.tiles.len() as u32 - 1
+	}
+
+	/// Gets if the edge leaves the node at pos in a direction.
+	fn leaves_node(&self, pos: [u16; 2], direction: direction::Direction8) -> bool {
+		let ends = self.get_ends();
+		(ends[0] == pos && self.directions[0] == direction) || (ends[1] == pos && self.directions[1] == direction)
+	}
+}
+
+/// A group of nodes that are connected to each other by roads but not to any other nodes.
+pub struct Component {
+	pub nodes: Vec<[u16; 2]>,
+	pub edges: Vec<EdgeId>,
+}
+
+fn get_size(tiles: &ndarray::Array2<tile::Tile>) -> [u16; 2] {
+	[tiles.dim().0 as u16, tiles.dim().1 as u16]
+}
+
+/// Gets the directions that a tile has a road link in along with the type of road on this tile's side of each link.
+pub fn get_connections(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Vec<(direction::Direction8, tile::Road, tile::Road)> {
+	let road_quarters = match tiles[[pos[0] as usize, pos[1] as usize]].cover.get_road_quarters() {
+		Some(valid) => valid,
+		None => return Vec::new(),
+	};
+	let mut out = Vec::new();
+	for direction in (0..8).map(direction::Direction8::new) {
+		let road = road_quarters[direction.get_int_val() as usize];
+		if road == tile::Road::None {
+			continue;
+		}
+		let [neighbor_x, neighbor_y] = match direction.offset_pos(pos, get_size(tiles)) {
+			Some(valid) => valid,
+			None => continue,
+		};
+		if let Some(neighbor_road_quarters) = tiles[[neighbor_x as usize, neighbor_y as usize]].cover.get_road_quarters() {
+			let neighbor_road = neighbor_road_quarters[direction.get_opposite().get_int_val() as usize];
+			if neighbor_road != tile::Road::None {
+				out.push((direction, road, neighbor_road));
+			}
+		}
+	}
+	out
+}
+
+/// Gets the sprites for the corners of a tile that diagonal roads between its neighbors cut across.
+fn get_corner_texture_ids(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> Vec<u8> {
+	let size = get_size(tiles);
+	let mut out = Vec::new();
+	for corner in [direction::Direction8::NorthEast, direction::Direction8::SouthEast, direction::Direction8::SouthWest, direction::Direction8::NorthWest] {
+		// The neighbors on either side of the corner, a link between them passes over it
+		let sides = [direction::Direction8::new((corner.get_int_val() + 7) % 8), direction::Direction8::new((corner.get_int_val() + 1) % 8)];
+		let (from, to) = match (sides[0].offset_pos(pos, size), sides[1].offset_pos(pos, size)) {
+			(Some(from), Some(to)) => (from, to),
+			_ => continue,
+		};
+		let link_direction = direction::Direction8::from_neighbors(from, to).unwrap();
+		if let Some((_, road, neighbor_road)) = get_connections(tiles, from).into_iter().find(|(direction, _, _)| *direction == link_direction) {
+			let road = match road.get_tier() >= neighbor_road.get_tier() {
+				true => road,
+				false => neighbor_road,
+			};
+			out.push(road.corner_texture().get_texture_id(corner));
+		}
+	}
+	out
+}
+
+/// Gets if a road tile should be a node, which is anything other than a tile that continues the same type of road in two directions.
+fn is_node(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2]) -> bool {
+	let connections = get_connections(tiles, pos);
+	connections.len() != 2 || connections[0].1 != connections[1].1 || connections.iter().any(|(_, road, neighbor_road)| road != neighbor_road)
+}
+
+pub struct RoadNetwork {
+	nodes: BTreeMap<[u16; 2], Node>,
+	edges: BTreeMap<EdgeId, Edge>,
+	/// Edge that each tile between two nodes belongs to.
+	edge_tiles: BTreeMap<[u16; 2], EdgeId>,
+	next_edge_id: EdgeId,
+	/// Sprites for the corners of tiles that diagonal roads cut across, kept so they are not found again every frame.
+	corner_texture_ids: BTreeMap<[u16; 2], Vec<u8>>,
+}
+
+impl RoadNetwork {
+	/// Builds the road network for every tile.
+	pub fn new(tiles: &ndarray::Array2<tile::Tile>) -> Self {
+		let mut out = Self { nodes: BTreeMap::new(), edges: BTreeMap::new(), edge_tiles: BTreeMap::new(), next_edge_id: 0, corner_texture_ids: BTreeMap::new() };
+		let positions: Vec<[u16; 2]> = tiles.indexed_iter()
+			.filter(|(_, tile)| tile.cover.get_road_quarters().is_some())
+			.map(|((x, y), _)| [x as u16, y as u16])
+			.collect();
+		out.update(tiles, &positions);
+		out
+	}
+
+	pub fn get_nodes(&self) -> &BTreeMap<[u16; 2], Node> {
+		&self.nodes
+	}
+
+	pub fn get_edges(&self) -> &BTreeMap<EdgeId, Edge> {
+		&self.edges
+	}
+
+	pub fn get_corner_texture_ids(&self) -> &BTreeMap<[u16; 2], Vec<u8>> {
+		&self.corner_texture_ids
+	}
+
+	/// Rebuilds the part of the network around tiles that have changed.
+	pub fn update(&mut self, tiles: &ndarray::Array2<tile::Tile>, changed_positions: &[[u16; 2]]) {
+		let size = get_size(tiles);
+
+		// Changing a tile can change if it or any of its neighbors are nodes
+		let mut affected = BTreeSet::new();
+		for pos in changed_positions {
+			affected.insert(*pos);
+			affected.extend((0..8).filter_map(|direction| direction::Direction8::new(direction).offset_pos(*pos, size)));
+		}
+
+		// A tile's corners depend on the links between its neighbors
+		for pos in &affected {
+			let ids = get_corner_texture_ids(tiles, *pos);
+			match ids.is_empty() {
+				true => self.corner_texture_ids.remove(pos),
+				false => self.corner_texture_ids.insert(*pos, ids),
+			};
+		}
+
+		// Remove the nodes and edges on affected tiles, remembering where they were so the network can be traced again from there
+		let mut dirty = BTreeSet::new();
+		let mut old_ends = BTreeSet::new();
+		for pos in affected {
+			if let Some(node) = self.nodes.remove(&pos) {
+				for edge_id in node.edges {
+					self.remove_edge(edge_id, &mut dirty, &mut old_ends);
+				}
+			}
+			if let Some(edge_id) = self.edge_tiles.get(&pos).copied() {
+				self.remove_edge(edge_id, &mut dirty, &mut old_ends);
+			}
+			dirty.insert(pos);
+		}
+
+		// Add nodes and trace edges from them
+		for pos in dirty.iter() {
+			if is_node(tiles, *pos) && tiles[[pos[0] as usize, pos[1] as usize]].cover.get_road_quarters().is_some() {
+				self.nodes.entry(*pos).or_insert(Node { edges: Vec::new() });
+			}
+		}
+		let starts: Vec<[u16; 2]> = dirty.iter().chain(old_ends.iter()).filter(|pos| self.nodes.contains_key(*pos)).copied().collect();
+		for pos in starts {
+			self.trace_from_node(tiles, pos);
+		}
+
+		// Road left over is in loops without any nodes, make one tile of each loop a node
+		for pos in dirty {
+			let is_road = !get_connections(tiles, pos).is_empty();
+			if is_road && !self.nodes.contains_key(&pos) && !self.edge_tiles.contains_key(&pos) {
+				self.nodes.insert(pos, Node { edges: Vec::new() });
+				self.trace_from_node(tiles, pos);
+			}
+		}
+	}
+
+	fn remove_edge(&mut self, edge_id: EdgeId, dirty: &mut BTreeSet<[u16; 2]>, old_ends: &mut BTreeSet<[u16; 2]>) {
+		let edge = match self.edges.remove(&edge_id) {
+			Some(valid) => valid,
+			None => return,
+		};
+		for end in edge.get_ends() {
+			if let Some(node) = self.nodes.get_mut(&end) {
+				node.edges.retain(|other_id| *other_id != edge_id);
+			}
+			old_ends.insert(end);
+		}
+		for pos in edge.tiles {
+			if self.edge_tiles.get(&pos) == Some(&edge_id) {
+				self.edge_tiles.remove(&pos);
+			}
+			dirty.insert(pos);
+		}
+	}
+
+	/// Traces an edge in every direction that a node has a road link in but no edge yet.
+	fn trace_from_node(&mut self, tiles: &ndarray::Array2<tile::Tile>, start: [u16; 2]) {
+		for (start_direction, road, _) in get_connections(tiles, start) {
+			let is_traced = self.nodes[&start].edges.iter().any(|edge_id| self.edges[edge_id].leaves_node(start, start_direction));
+			if is_traced {
+				continue;
+			}
+
+			// Follow the road until reaching a node
+			let mut edge_tiles = vec![start];
+			let mut pos = start;
+			let mut direction = start_direction;
+			loop {
+				pos = direction.offset_pos(pos, get_size(tiles)).unwrap();
+				edge_tiles.push(pos);
+				if self.nodes.contains_key(&pos) {
+					break;
+				}
+				let came_from = direction.get_opposite();
+				direction = get_connections(tiles, pos).into_iter().map(|(direction, _, _)| direction).find(|direction| *direction != came_from).unwrap();
+			}
+
+			let edge_id = self.next_edge_id;
+			self.next_edge_id += 1;
+			for pos in &edge_tiles[1..edge_tiles.len() - 1] {
+				self.edge_tiles.insert(*pos, edge_id);
+			}
+			let end = pos;
+			self.nodes.get_mut(&start).unwrap().edges.push(edge_id);
+			if end != start {
+				self.nodes.get_mut(&end).unwrap().edges.push(edge_id);
+			}
+			self.edges.insert(edge_id, Edge { tiles: edge_tiles, directions: [start_direction, direction.get_opposite()], road });
+		}
+	}
+
+	/// Gets if two networks have the same nodes, edges and corner sprites, the edge ids can differ.
+	pub fn matches(&self, other: &RoadNetwork) -> bool {
+		let get_sorted_edges = |network: &RoadNetwork| {
+			let mut edges: Vec<(Vec<[u16; 2]>, tile::Road)> = network.edges.values()
+				.map(|edge| (edge.tiles.clone().min(edge.tiles.iter().rev().copied().collect()), edge.road))
+				.collect();
+			edges.sort_by(|a, b| a.0.cmp(&b.0));
+			edges
+		};
+		self.nodes.keys().eq(other.nodes.keys()) && get_sorted_edges(self) == get_sorted_edges(other) && self.corner_texture_ids == other.corner_texture_ids
+	}
+
+	/// Groups the nodes into sets that are connected by roads, any group other than the largest is isolated from the rest.
+	pub fn get_components(&self) -> Vec<Component> {
+		let mut visited = BTreeSet::new();
+		let mut components = Vec::new();
+		for start in self.nodes.keys() {
+			if visited.contains(start) {
+				continue;
+			}
+			let mut component = Component { nodes: Vec::new(), edges: Vec::new() };
+			let mut queue = VecDeque::from([*start]);
+			visited.insert(*start);
+			while let Some(pos) = queue.pop_front() {
+				component.nodes.push(pos);
+				for edge_id in &self.nodes[&pos].edges {
+					if !component.edges.contains(edge_id) {
+						component.edges.push(*edge_id);
+					}
+					for end in self.edges[edge_id].get_ends() {
+						if visited.insert(end) {
+							queue.push_back(end);
+						}
+					}
+				}
+			}
+			components.push(component);
+		}
+		components.sort_by_key(|component| std::cmp::Reverse(component.nodes.len()));
+		components
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::interaction::{InteractionShape, RoadChange, RoadDrag, TileInteraction};
+	use crate::mouse;
+
+	/// Drags an interaction from one tile to another, updates the network from the tiles it touched and checks it against a rebuilt one.
+	fn drag(tiles: &mut ndarray::Array2<tile::Tile>, network: &mut RoadNetwork, shape: InteractionShape, from: [u16; 2], to: [u16; 2]) {
+		let mouse = mouse::Mouse { pos: to, click_start: from, gui_pos: [0, 0], is_left_clicking: true, is_middle_clicking: false, is_right_clicking: false };
+		let changed_positions: Vec<[u16; 2]> = shape.interact(tiles, &mouse).into_iter().map(|(pos, _)| pos).collect();
+		network.update(tiles, &changed_positions);
+		assert!(network.matches(&RoadNetwork::new(tiles)));
+	}
+
+	#[test]
+	fn updates_match_rebuilding() {
+		let mut tiles = ndarray::Array2::from_elem((24, 24), tile::Tile::new());
+		let mut network = RoadNetwork::new(&tiles);
+		let road = InteractionShape::RoadLine(tile::Road::Gravel, RoadDrag::Straight);
+
+		// A loop, a diagonal from one of its corners out past the opposite one, and a road crossing both
+		drag(&mut tiles, &mut network, road, [2, 2], [12, 2]);
+		drag(&mut tiles, &mut network, road, [12, 2], [12, 12]);
+		drag(&mut tiles, &mut network, road, [12, 12], [2, 12]);
+		drag(&mut tiles, &mut network, road, [2, 12], [2, 2]);
+		drag(&mut tiles, &mut network, road, [2, 2], [18, 18]);
+		drag(&mut tiles, &mut network, road, [7, 0], [7, 20]);
+		drag(&mut tiles, &mut network, InteractionShape::ChangeRoadLine(RoadChange::Upgrade), [2, 12], [2, 2]);
+		assert!(network.get_nodes().len() > 4);
+
+		// Cut the loop and the diagonal, then join a corner back up and join a new road to the diagonal
+		drag(&mut tiles, &mut network, InteractionShape::EraseRoadLine, [4, 2], [6, 2]);
+		drag(&mut tiles, &mut network, InteractionShape::EraseRoadLine, [5, 5], [6, 6]);
+		drag(&mut tiles, &mut network, InteractionShape::Dot(TileInteraction::DemolishCover), [12, 12], [12, 12]);
+		drag(&mut tiles, &mut network, InteractionShape::Rectangle(TileInteraction::DemolishCover), [6, 10], [8, 14]);
+		drag(&mut tiles, &mut network, InteractionShape::Dot(TileInteraction::ConnectRoad(tile::Road::Paved)), [12, 12], [12, 12]);
+		drag(&mut tiles, &mut network, InteractionShape::Dot(TileInteraction::ConnectRoad(tile::Road::Paved)), [16, 14], [16, 14]);
+		assert!(tiles[[16, 14]].cover.get_road_quarters().is_some());
+	}
+}
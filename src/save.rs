@@ -0,0 +1,60 @@
+use std::io;
+
+/// Bytes at the start of every world file.
+pub const MAGIC: [u8; 4] = *b"GSIM";
+/// Version of the world file format that gets written, older versions can still be loaded.
+pub const FORMAT_VERSION: u16 = 13;
+
+pub fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn write_u8(writer: &mut impl io::Write, value: u8) -> io::Result<()> {
+	writer.write_all(&[value])
+}
+
+pub fn write_u16(writer: &mut impl io::Write, value: u16) -> io::Result<()> {
+	writer.write_all(&value.to_le_bytes())
+}
+
+pub fn write_u64(writer: &mut impl io::Write, value: u64) -> io::Result<()> {
+	writer.write_all(&value.to_le_bytes())
+}
+
+pub fn read_u8(reader: &mut impl io::Read) -> io::Result<u8> {
+	let mut bytes = [0u8; 1];
+	reader.read_exact(&mut bytes)?;
+	Ok(bytes[0])
+}
+
+pub fn read_u16(reader: &mut impl io::Read) -> io::Result<u16> {
+	let mut bytes = [0u8; 2];
+	reader.read_exact(&mut bytes)?;
+	Ok(u16::from_le_bytes(bytes))
+}
+
+pub fn read_u64(reader: &mut impl io::Read) -> io::Result<u64> {
+	let mut bytes = [0u8; 8];
+	reader.read_exact(&mut bytes)?;
+	Ok(u64::from_le_bytes(bytes))
+}
+
+/// Writes the magic number and current format version.
+pub fn write_header(writer: &mut impl io::Write) -> io::Result<()> {
+	writer.write_all(&MAGIC)?;
+	write_u16(writer, FORMAT_VERSION)
+}
+
+/// Checks the magic number and returns the format version of the file.
+pub fn read_header(reader: &mut impl io::Read) -> io::Result<u16> {
+	let mut magic = [0u8; 4];
+	reader.read_exact(&mut magic)?;
+	if magic != MAGIC {
+		return Err(invalid_data("not a world file".to_string()));
+	}
+	let version = read_u16(reader)?;
+	if version == 0 || version > FORMAT_VERSION {
+		return Err(invalid_data(format!("unsupported world file version {version}")));
+	}
+	Ok(version)
+}
@@ -0,0 +1,56 @@
+use std::time;
+
+/// Real time between simulation ticks at normal speed.
+pub const TICK_DURATION: time::Duration = time::Duration::from_millis(100);
+// Most ticks that are run to catch up in one frame, more are dropped so a slow frame does not make the next one slower
+const MAX_TICKS_PER_UPDATE: u32 = 4;
+
+#[derive(Copy, Clone)]
+pub enum SimulationSpeed {
+	Normal,
+	Double,
+	Quadruple,
+}
+
+impl SimulationSpeed {
+	fn get_multiplier(self) -> u32 {
+		match self {
+			SimulationSpeed::Normal => 1,
+			SimulationSpeed::Double => 2,
+			SimulationSpeed::Quadruple => 4,
+		}
+	}
+}
+
+/// Decides when simulation ticks happen, separate from how often frames are drawn.
+pub struct Clock {
+	pub speed: SimulationSpeed,
+	pub is_paused: bool,
+	last_update: time::Instant,
+	accumulated: time::Duration,
+}
+
+impl Clock {
+	pub fn new() -> Self {
+		Self { speed: SimulationSpeed::Normal, is_paused: false, last_update: time::Instant::now(), accumulated: time::Duration::ZERO }
+	}
+
+	/// Gets how far it is through the next tick, from 0 to 1.
+	pub fn get_tick_fraction(&self) -> f32 {
+		(self.accumulated.as_secs_f32() / TICK_DURATION.as_secs_f32()).min(1.)
+	}
+
+	/// Gets how many ticks should be run since the last update.
+	pub fn update(&mut self) -> u32 {
+		let now = time::Instant::now();
+		let elapsed = now - self.last_update;
+		self.last_update = now;
+		if self.is_paused {
+			return 0;
+		}
+		self.accumulated += elapsed * self.speed.get_multiplier();
+		let ticks = (self.accumulated.as_nanos() / TICK_DURATION.as_nanos()) as u32;
+		self.accumulated -= TICK_DURATION * ticks;
+		ticks.min(MAX_TICKS_PER_UPDATE)
+	}
+}
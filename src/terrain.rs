@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+
+use super::random;
+use super::tile;
+use super::world;
+
+// Salts so that each noise layer is different for the same seed
+const ELEVATION_SALT: u64 = 0;
+const MOISTURE_SALT: u64 = 1;
+const GRAVEL_SALT: u64 = 2;
+const FOREST_SALT: u64 = 3;
+const TREE_SALT: u64 = 4;
+
+/// Settings for generating a world, the same parameters always generate the same world.
+#[derive(Copy, Clone)]
+pub struct TerrainParameters {
+	pub seed: u64,
+	/// Fraction of the world that is under water, from 0 to 1.
+	pub water_level: f32,
+	/// Fraction of the world just above the water level where wetlands can form.
+	pub swamp_height: f32,
+	/// How many tiles of sand surround water.
+	pub beach_width: u16,
+	/// Fraction of land covered in gravel patches, from 0 to 1.
+	pub gravel_density: f32,
+	/// Fraction of land covered in forests, from 0 to 1.
+	pub forest_density: f32,
+	/// Rough size in tiles of the largest lakes and forests.
+	pub feature_size: f32,
+}
+
+impl Default for TerrainParameters {
+	fn default() -> Self {
+		Self {
+			seed: 0,
+			water_level: 0.35,
+			swamp_height: 0.1,
+			beach_width: 1,
+			gravel_density: 0.05,
+			forest_density: 0.3,
+			feature_size: 64.,
+		}
+	}
+}
+
+/// Value noise at a point, smoothly interpolated between random values at whole coordinates.
+fn value_noise(seed: u64, salt: u64, x: f32, y: f32) -> f32 {
+	let x_floor = x.floor();
+	let y_floor = y.floor();
+	let corner = |x_offset: f32, y_offset: f32| {
+		random::hash_unit(seed, &[salt, (x_floor + x_offset) as i64 as u64, (y_floor + y_offset) as i64 as u64])
+	};
+	let smooth = |t: f32| t * t * (3. - 2. * t);
+	let x_t = smooth(x - x_floor);
+	let y_t = smooth(y - y_floor);
+	let top = corner(0., 0.) + (corner(1., 0.) - corner(0., 0.)) * x_t;
+	let bottom = corner(0., 1.) + (corner(1., 1.) - corner(0., 1.)) * x_t;
+	top + (bottom - top) * y_t
+}
+
+/// Several layers of value noise at halving sizes, normalized to 0 to 1.
+fn layered_noise(seed: u64, salt: u64, pos: [u16; 2], feature_size: f32) -> f32 {
+	let mut total = 0.;
+	let mut amplitude = 1.;
+	let mut max_total = 0.;
+	let mut size = feature_size.max(1.);
+	for octave in 0..4 {
+		total += value_noise(seed, salt * 16 + octave, pos[0] as f32 / size, pos[1] as f32 / size) * amplitude;
+		max_total += amplitude;
+		amplitude /= 2.;
+		size /= 2.;
+	}
+	total / max_total
+}
+
+/// Finds the tiles within max_distance of water.
+fn get_shore(tiles: &ndarray::Array2<tile::Tile>, max_distance: u16) -> ndarray::Array2<bool> {
+	let mut is_shore = ndarray::Array2::from_elem(tiles.dim(), false);
+	let mut distances = ndarray::Array2::from_elem(tiles.dim(), u16::MAX);
+	let mut queue = VecDeque::new();
+	for ((x, y), tile) in tiles.indexed_iter() {
+		if tile.ground == tile::Ground::Water {
+			distances[[x, y]] = 0;
+			queue.push_back([x, y]);
+		}
+	}
+	while let Some([x, y]) = queue.pop_front() {
+		let distance = distances[[x, y]];
+		if distance >= max_distance {
+			continue;
+		}
+		let neighbors = [[x.wrapping_sub(1), y], [x + 1, y], [x, y.wrapping_sub(1)], [x, y + 1]];
+		for [neighbor_x, neighbor_y] in neighbors {
+			if neighbor_x < tiles.dim().0 && neighbor_y < tiles.dim().1 && distances[[neighbor_x, neighbor_y]] == u16::MAX {
+				distances[[neighbor_x, neighbor_y]] = distance + 1;
+				is_shore[[neighbor_x, neighbor_y]] = true;
+				queue.push_back([neighbor_x, neighbor_y]);
+			}
+		}
+	}
+	is_shore
+}
+
+/// Gets the value that the given fraction of values are below.
+fn get_threshold(values: &ndarray::Array2<f32>, fraction: f32) -> f32 {
+	let mut sorted: Vec<f32> = values.iter().copied().collect();
+	sorted.sort_by(f32::total_cmp);
+	match fraction <= 0. {
+		true => f32::NEG_INFINITY,
+		false => sorted[((fraction * sorted.len() as f32) as usize).min(sorted.len() - 1)],
+	}
+}
+
+/// Generates a noise value for every tile of a world.
+fn generate_noise(world: &world::World, seed: u64, salt: u64, feature_size: f32) -> ndarray::Array2<f32> {
+	ndarray::Array2::from_shape_fn(world.tiles.dim(), |(x, y)| layered_noise(seed, salt, [x as u16, y as u16], feature_size))
+}
+
+/// Generates a new world with lakes, wetlands, beaches, gravel and forests.
+pub fn generate(width: u16, height: u16, parameters: &TerrainParameters) -> world::World {
+	let mut world = world::World::new(width, height);
+	let seed = parameters.seed;
+	world.seed = seed;
+	let feature_size = parameters.feature_size;
+	let elevation = generate_noise(&world, seed, ELEVATION_SALT, feature_size);
+	let moisture = generate_noise(&world, seed, MOISTURE_SALT, feature_size / 2.);
+	let gravel = generate_noise(&world, seed, GRAVEL_SALT, feature_size / 4.);
+	let forest = generate_noise(&world, seed, FOREST_SALT, feature_size / 2.);
+	let water_threshold = get_threshold(&elevation, parameters.water_level);
+	let swamp_threshold = get_threshold(&elevation, parameters.water_level + parameters.swamp_height);
+	let gravel_threshold = get_threshold(&gravel, parameters.gravel_density);
+	let forest_threshold = get_threshold(&forest, parameters.forest_density);
+	let dense_forest_threshold = get_threshold(&forest, parameters.forest_density / 2.);
+
+	// Lay out water, wetlands and dry ground from elevation and moisture
+	for ((x, y), tile) in world.tiles.indexed_iter_mut() {
+		tile.height = (elevation[[x, y]] * u8::MAX as f32) as u8;
+		tile.ground = if elevation[[x, y]] < water_threshold {
+			tile::Ground::Water
+		}
+		else if elevation[[x, y]] < swamp_threshold && moisture[[x, y]] > 0.5 {
+			tile::Ground::Swamp
+		}
+		else if gravel[[x, y]] < gravel_threshold {
+			tile::Ground::Gravel
+		}
+		else {
+			tile::Ground::Grass
+		};
+	}
+
+	// Put beaches next to water
+	let is_shore = get_shore(&world.tiles, parameters.beach_width);
+	for ((x, y), tile) in world.tiles.indexed_iter_mut() {
+		if is_shore[[x, y]] && tile.ground.is_land() {
+			tile.ground = tile::Ground::Sand;
+		}
+	}
+
+	// Grow forests with leaf litter under their dense middles
+	for ((x, y), tile) in world.tiles.indexed_iter_mut() {
+		if forest[[x, y]] >= forest_threshold {
+			continue;
+		}
+		let is_dense = forest[[x, y]] < dense_forest_threshold;
+		if is_dense && tile.ground == tile::Ground::Grass {
+			tile.ground = tile::Ground::LeafLitter;
+		}
+		let tree_random = random::hash_unit(seed, &[TREE_SALT, x as u64, y as u64]);
+		let stage = match (is_dense, tree_random) {
+			(_, random) if random >= 0.8 => continue,
+			(true, random) if random < 0.4 => tile::TreeStage::Old,
+			(_, random) if random < 0.1 => tile::TreeStage::Sapling,
+			_ => tile::TreeStage::Mature,
+		};
+		let cover = tile::Cover::Tree(stage);
+		if cover.can_go_on_ground(tile.ground) {
+			tile.cover = cover;
+		}
+	}
+
+	world
+}
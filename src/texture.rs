@@ -1,11 +1,31 @@
+use std::io::Cursor;
+
 use super::vertex;
 use super::direction;
 
+/// Width and height in pixels of each sprite.
+pub const SPRITE_SIZE: u32 = 16;
+
+/// Loads the image that holds every sprite, 16 by 16 sprites in rows from the top left.
+pub fn load_atlas() -> image::RgbaImage {
+	image::load(Cursor::new(&include_bytes!("textures.png")), image::ImageFormat::Png).unwrap().to_rgba8()
+}
+
+/// Gets the pixel position of the top left of a sprite in the atlas.
+pub fn get_atlas_pos(id: u8) -> [u32; 2] {
+	[(id % 16) as u32 * SPRITE_SIZE, (id >> 4) as u32 * SPRITE_SIZE]
+}
+
 pub fn generate_tris_from_id(id: u8, pos: [u16; 2]) -> [vertex::Vertex; 6] {
-	let x_start = (pos[0] * 16) as f32;
-	let x_end = ((pos[0] + 1) * 16) as f32;
-	let y_start = (pos[1] * 16) as f32;
-	let y_end = ((pos[1] + 1) * 16) as f32;
+	generate_tris_from_id_at(id, [pos[0] as f32, pos[1] as f32])
+}
+
+/// Generates the tris for a sprite at a position measured in tiles that does not have to line up with the tile grid.
+pub fn generate_tris_from_id_at(id: u8, pos: [f32; 2]) -> [vertex::Vertex; 6] {
+	let x_start = pos[0] * 16.;
+	let x_end = (pos[0] + 1.) * 16.;
+	let y_start = pos[1] * 16.;
+	let y_end = (pos[1] + 1.) * 16.;
 
 	let texture_column = id % 16;
 	let texture_row = id >> 4;
@@ -48,7 +68,7 @@ pub enum Texture {
 	Bricks,
 	Select,
 	Tree,
-	TestBuilding,
+	House,
 	SelectBuildable,
 	SelectUnbuildable,
 	SelectDestroy,
@@ -58,12 +78,45 @@ pub enum Texture {
 	LeafLitter,
 	Swamp,
 	Sand,
+	Sapling,
+	OldTree,
+	Fire,
+	Ash,
+	Rubble,
+	ResidentialZone,
+	CommercialZone,
+	IndustrialZone,
+	Shop,
+	Factory,
+	/// First of the digit sprites from 0 to 9 which follow each other.
+	Digit0,
+	MoneySign,
+	MoneySignRed,
+	Minus,
+	/// Top left of the town hall facing south, the parts for the other facings are elsewhere.
+	TownHall,
+	TownHallNorth,
+	TownHallEast,
+	TownHallWest,
+	Warehouse,
+	WarehouseNorth,
+	WarehouseEast,
+	WarehouseWest,
+	BridgeNorthSouth,
+	BridgeEastWest,
+	DirtRoadGUI,
+	PavedRoadGUI,
+	HighwayGUI,
+	RoadUpgrade,
+	RoadDowngrade,
+	ConnectRoadGUI,
+	EraseRoadGUI,
 	//YellowThing,
 	//GreenThing
 }
 
 impl Texture {
-	fn get_texture_id(self) -> u8 {
+	pub fn get_texture_id(self) -> u8 {
 		match self {
 			//Self::Test => 0x00,
 			Self::Grass => 0x01,
@@ -71,7 +124,7 @@ impl Texture {
 			Self::Bricks => 0x03,
 			Self::Select => 0x04,
 			Self::Tree => 0x05,
-			Self::TestBuilding => 0x06,
+			Self::House => 0x06,
 			Self::SelectBuildable => 0x07,
 			Self::SelectUnbuildable => 0x08,
 			Self::SelectDestroy => 0x09,
@@ -81,6 +134,37 @@ impl Texture {
 			Self::LeafLitter => 0x11,
 			Self::Swamp => 0x12,
 			Self::Sand => 0x13,
+			Self::Sapling => 0x14,
+			Self::OldTree => 0x15,
+			Self::Fire => 0x16,
+			Self::Ash => 0x17,
+			Self::Rubble => 0x18,
+			Self::ResidentialZone => 0x1D,
+			Self::CommercialZone => 0x1E,
+			Self::IndustrialZone => 0x1F,
+			Self::Shop => 0x20,
+			Self::Factory => 0x21,
+			Self::Digit0 => 0x22,
+			Self::MoneySign => 0x2C,
+			Self::MoneySignRed => 0x2D,
+			Self::Minus => 0x2E,
+			Self::TownHall => 0x30,
+			Self::TownHallNorth => 0x92,
+			Self::TownHallEast => 0x90,
+			Self::TownHallWest => 0x94,
+			Self::Warehouse => 0x32,
+			Self::WarehouseNorth => 0x96,
+			Self::WarehouseEast => 0x99,
+			Self::WarehouseWest => 0x9B,
+			Self::BridgeNorthSouth => 0x35,
+			Self::BridgeEastWest => 0x36,
+			Self::RoadDowngrade => 0x37,
+			Self::ConnectRoadGUI => 0x38,
+			Self::EraseRoadGUI => 0x39,
+			Self::DirtRoadGUI => 0x6C,
+			Self::PavedRoadGUI => 0x6D,
+			Self::HighwayGUI => 0x6E,
+			Self::RoadUpgrade => 0x6F,
 			//Self::YellowThing => 0xF0,
     		//Self::GreenThing => 0xFF,
 		}
@@ -93,17 +177,68 @@ impl Texture {
 
 #[derive(Copy, Clone)]
 pub enum Texture4Directional {
-	GravelRoad
+	GravelRoad,
+	DirtRoad,
+	PavedRoad,
+	Highway,
+	Vehicle,
+	House,
+	Shop,
+	Factory,
+	/// Arrow on zoned land pointing to the side that needs a road.
+	ZoneArrow,
 }
 
 impl Texture4Directional {
-	fn get_texture_id(self) -> u8 {
+	fn get_base_texture_id(self) -> u8 {
 		match self {
 			Texture4Directional::GravelRoad => 0x0C,
+			Texture4Directional::DirtRoad => 0x60,
+			Texture4Directional::PavedRoad => 0x64,
+			Texture4Directional::Highway => 0x68,
+			Texture4Directional::Vehicle => 0x19,
+			Texture4Directional::House => 0x50,
+			Texture4Directional::Shop => 0x54,
+			Texture4Directional::Factory => 0x58,
+			Texture4Directional::ZoneArrow => 0x5C,
+		}
+	}
+
+	pub fn get_texture_id(self, direction: direction::Direction4) -> u8 {
+		self.get_base_texture_id() + direction.get_int_val()
+	}
+}
+
+/// Sprites with a version for each diagonal direction, in the order north east, south east, south west, north west.
+#[derive(Copy, Clone)]
+pub enum Texture4Diagonal {
+	DirtRoad,
+	GravelRoad,
+	PavedRoad,
+	Highway,
+	/// Corner of a tile that a diagonal road between two of its neighbors cuts across.
+	DirtRoadCorner,
+	GravelRoadCorner,
+	PavedRoadCorner,
+	HighwayCorner,
+}
+
+impl Texture4Diagonal {
+	fn get_base_texture_id(self) -> u8 {
+		match self {
+			Texture4Diagonal::DirtRoad => 0x70,
+			Texture4Diagonal::GravelRoad => 0x74,
+			Texture4Diagonal::PavedRoad => 0x78,
+			Texture4Diagonal::Highway => 0x7C,
+			Texture4Diagonal::DirtRoadCorner => 0x80,
+			Texture4Diagonal::GravelRoadCorner => 0x84,
+			Texture4Diagonal::PavedRoadCorner => 0x88,
+			Texture4Diagonal::HighwayCorner => 0x8C,
 		}
 	}
 
-	pub fn generate_tris(self, direction: direction::Direction4, pos: [u16; 2]) -> [vertex::Vertex; 6] {
-		generate_tris_from_id(self.get_texture_id() + direction.get_int_val(), pos)
+	/// Gets the sprite for a diagonal direction.
+	pub fn get_texture_id(self, direction: direction::Direction8) -> u8 {
+		self.get_base_texture_id() + direction.get_int_val() / 2
 	}
 }
\ No newline at end of file
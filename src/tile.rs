@@ -1,6 +1,8 @@
+use std::io;
 use super::texture;
 use super::vertex;
 use super::direction;
+use super::save;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Ground {
@@ -11,6 +13,7 @@ pub enum Ground {
 	LeafLitter,
 	Swamp,
 	Sand,
+	Ash,
 }
 
 impl Ground {
@@ -23,10 +26,11 @@ impl Ground {
 			Ground::LeafLitter => texture::Texture::LeafLitter,
 			Ground::Swamp => texture::Texture::Swamp,
 			Ground::Sand => texture::Texture::Sand,
+			Ground::Ash => texture::Texture::Ash,
 		}
 	}
 
-	fn is_land(self) -> bool {
+	pub fn is_land(self) -> bool {
 		match self {
 			Ground::Grass => true,
 			Ground::Water => false,
@@ -35,10 +39,53 @@ impl Ground {
 			Ground::LeafLitter => true,
 			Ground::Swamp => false,
 			Ground::Sand => true,
+			Ground::Ash => true,
 		}
 	}
 
-	fn is_water(self) -> bool {
+	/// Gets the color of this ground for one pixel per tile images.
+	pub fn get_map_color(self) -> [u8; 3] {
+		match self {
+			Ground::Grass => [0x50, 0xC8, 0x50],
+			Ground::Water => [0x28, 0x50, 0xDC],
+			Ground::Bricks => [0xB4, 0x78, 0x50],
+			Ground::Gravel => [0x8C, 0x8C, 0x8C],
+			Ground::LeafLitter => [0x6E, 0x50, 0x28],
+			Ground::Swamp => [0x46, 0x6E, 0x46],
+			Ground::Sand => [0xF0, 0xDC, 0x78],
+			Ground::Ash => [0x3A, 0x3A, 0x3A],
+		}
+	}
+
+	/// Gets the cost of moving onto this ground off road, None if it cannot be crossed.
+	pub fn get_movement_cost(self) -> Option<u32> {
+		match self {
+			Ground::Grass => Some(10),
+			Ground::Water => None,
+			Ground::Bricks => Some(8),
+			Ground::Gravel => Some(10),
+			Ground::LeafLitter => Some(14),
+			Ground::Swamp => Some(50),
+			Ground::Sand => Some(16),
+			Ground::Ash => Some(12),
+		}
+	}
+
+	/// Gets the cost of replacing a tile's ground with this ground.
+	pub fn get_build_cost(self) -> i64 {
+		match self {
+			Ground::Grass => 1000,
+			Ground::Water => 5000,
+			Ground::Bricks => 2500,
+			Ground::Gravel => 1000,
+			Ground::LeafLitter => 1000,
+			Ground::Swamp => 3000,
+			Ground::Sand => 1000,
+			Ground::Ash => 500,
+		}
+	}
+
+	pub fn is_water(self) -> bool {
 		match self {
 			Ground::Grass => false,
 			Ground::Water => true,
@@ -47,10 +94,11 @@ impl Ground {
 			Ground::LeafLitter => false,
 			Ground::Swamp => true,
 			Ground::Sand => false,
+			Ground::Ash => false,
 		}
 	}
 
-	fn is_fertile(self) -> bool {
+	pub fn is_fertile(self) -> bool {
 		match self {
 			Ground::Grass => true,
 			Ground::Water => false,
@@ -59,75 +107,764 @@ impl Ground {
 			Ground::LeafLitter => true,
 			Ground::Swamp => true,
 			Ground::Sand => false,
+			Ground::Ash => true,
+		}
+	}
+
+	fn get_save_id(self) -> u8 {
+		match self {
+			Ground::Grass => 0,
+			Ground::Water => 1,
+			Ground::Bricks => 2,
+			Ground::Gravel => 3,
+			Ground::LeafLitter => 4,
+			Ground::Swamp => 5,
+			Ground::Sand => 6,
+			Ground::Ash => 7,
 		}
 	}
+
+	fn from_save_id(id: u8) -> io::Result<Self> {
+		Ok(match id {
+			0 => Ground::Grass,
+			1 => Ground::Water,
+			2 => Ground::Bricks,
+			3 => Ground::Gravel,
+			4 => Ground::LeafLitter,
+			5 => Ground::Swamp,
+			6 => Ground::Sand,
+			7 => Ground::Ash,
+			_ => return Err(save::invalid_data(format!("unknown ground id {id}"))),
+		})
+	}
 }
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Road {
 	None,
+	DirtPath,
 	Gravel,
+	Paved,
+	Highway,
 }
 
 impl Road {
 	fn texture(self) -> texture::Texture4Directional {
 		match self {
 			Road::None => texture::Texture4Directional::GravelRoad,
+			Road::DirtPath => texture::Texture4Directional::DirtRoad,
 			Road::Gravel => texture::Texture4Directional::GravelRoad,
+			Road::Paved => texture::Texture4Directional::PavedRoad,
+			Road::Highway => texture::Texture4Directional::Highway,
+		}
+	}
+
+	fn diagonal_texture(self) -> texture::Texture4Diagonal {
+		match self {
+			Road::None => texture::Texture4Diagonal::GravelRoad,
+			Road::DirtPath => texture::Texture4Diagonal::DirtRoad,
+			Road::Gravel => texture::Texture4Diagonal::GravelRoad,
+			Road::Paved => texture::Texture4Diagonal::PavedRoad,
+			Road::Highway => texture::Texture4Diagonal::Highway,
+		}
+	}
+
+	/// Gets the sprites for the corners of tiles that a diagonal link of this road cuts across.
+	pub fn corner_texture(self) -> texture::Texture4Diagonal {
+		match self {
+			Road::None => texture::Texture4Diagonal::GravelRoadCorner,
+			Road::DirtPath => texture::Texture4Diagonal::DirtRoadCorner,
+			Road::Gravel => texture::Texture4Diagonal::GravelRoadCorner,
+			Road::Paved => texture::Texture4Diagonal::PavedRoadCorner,
+			Road::Highway => texture::Texture4Diagonal::HighwayCorner,
+		}
+	}
+
+	/// Gets the sprite for a quarter of this road going in a direction.
+	fn get_quarter_texture_id(self, direction: direction::Direction8) -> u8 {
+		match direction.get_direction4() {
+			Some(direction4) => self.texture().get_texture_id(direction4),
+			None => self.diagonal_texture().get_texture_id(direction),
+		}
+	}
+
+	/// Gets how good the road is, better roads are drawn on top of worse ones where they meet.
+	pub fn get_tier(self) -> u8 {
+		match self {
+			Road::None => 0,
+			Road::DirtPath => 1,
+			Road::Gravel => 2,
+			Road::Paved => 3,
+			Road::Highway => 4,
+		}
+	}
+
+	/// Gets the road one tier better, None if there is none.
+	pub fn get_upgrade(self) -> Option<Self> {
+		match self {
+			Road::None => None,
+			Road::DirtPath => Some(Road::Gravel),
+			Road::Gravel => Some(Road::Paved),
+			Road::Paved => Some(Road::Highway),
+			Road::Highway => None,
+		}
+	}
+
+	/// Gets the road one tier worse, None if there is none.
+	pub fn get_downgrade(self) -> Option<Self> {
+		match self {
+			Road::None => None,
+			Road::DirtPath => None,
+			Road::Gravel => Some(Road::DirtPath),
+			Road::Paved => Some(Road::Gravel),
+			Road::Highway => Some(Road::Paved),
+		}
+	}
+
+	/// Gets the cost of driving along this road for one tile.
+	pub fn get_movement_cost(self) -> u32 {
+		match self {
+			Road::None => u32::MAX,
+			Road::DirtPath => 8,
+			Road::Gravel => 4,
+			Road::Paved => 3,
+			Road::Highway => 2,
+		}
+	}
+
+	/// Gets the cost of driving along this road to the neighboring tile in a direction, diagonal links are longer.
+	pub fn get_link_movement_cost(self, direction: direction::Direction8) -> u32 {
+		match direction.is_diagonal() {
+			true => self.get_movement_cost() * 3 / 2,
+			false => self.get_movement_cost(),
+		}
+	}
+
+	/// Gets the cost of building one quarter of this road.
+	pub fn get_build_cost(self) -> i64 {
+		match self {
+			Road::None => 0,
+			Road::DirtPath => 300,
+			Road::Gravel => 1000,
+			Road::Paved => 2500,
+			Road::Highway => 5000,
+		}
+	}
+
+	/// Gets the cost of removing one quarter of this road.
+	pub fn get_demolish_cost(self) -> i64 {
+		match self {
+			Road::None => 0,
+			Road::DirtPath => 100,
+			Road::Gravel => 200,
+			Road::Paved => 500,
+			Road::Highway => 1000,
 		}
 	}
+
+	/// Gets the cost of keeping one quarter of this road each tick.
+	pub fn get_upkeep(self) -> i64 {
+		match self {
+			Road::None => 0,
+			Road::DirtPath => 0,
+			Road::Gravel => 1,
+			Road::Paved => 2,
+			Road::Highway => 4,
+		}
+	}
+
+	pub fn get_name(self) -> &'static str {
+		match self {
+			Road::None => "no road",
+			Road::DirtPath => "dirt path",
+			Road::Gravel => "gravel road",
+			Road::Paved => "paved road",
+			Road::Highway => "highway",
+		}
+	}
+
+	fn get_save_id(self) -> u8 {
+		match self {
+			Road::None => 0,
+			Road::Gravel => 1,
+			Road::DirtPath => 2,
+			Road::Paved => 3,
+			Road::Highway => 4,
+		}
+	}
+
+	fn from_save_id(id: u8) -> io::Result<Self> {
+		Ok(match id {
+			0 => Road::None,
+			1 => Road::Gravel,
+			2 => Road::DirtPath,
+			3 => Road::Paved,
+			4 => Road::Highway,
+			_ => return Err(save::invalid_data(format!("unknown road id {id}"))),
+		})
+	}
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum TreeStage {
+	Sapling,
+	Mature,
+	Old,
+}
+
+impl TreeStage {
+	fn texture(self) -> texture::Texture {
+		match self {
+			TreeStage::Sapling => texture::Texture::Sapling,
+			TreeStage::Mature => texture::Texture::Tree,
+			TreeStage::Old => texture::Texture::OldTree,
+		}
+	}
+
+	/// Gets the stage that a tree grows into, None for old trees which do not grow any more.
+	pub fn get_next(self) -> Option<Self> {
+		match self {
+			TreeStage::Sapling => Some(TreeStage::Mature),
+			TreeStage::Mature => Some(TreeStage::Old),
+			TreeStage::Old => None,
+		}
+	}
+
+	fn get_save_id(self) -> u8 {
+		match self {
+			TreeStage::Sapling => 0,
+			TreeStage::Mature => 1,
+			TreeStage::Old => 2,
+		}
+	}
+
+	fn from_save_id(id: u8) -> io::Result<Self> {
+		Ok(match id {
+			0 => TreeStage::Sapling,
+			1 => TreeStage::Mature,
+			2 => TreeStage::Old,
+			_ => return Err(save::invalid_data(format!("unknown tree stage id {id}"))),
+		})
+	}
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Zone {
+	Residential,
+	Commercial,
+	Industrial,
+}
+
+impl Zone {
+	pub const ALL: [Zone; 3] = [Zone::Residential, Zone::Commercial, Zone::Industrial];
+
+	fn zone_texture(self) -> texture::Texture {
+		match self {
+			Zone::Residential => texture::Texture::ResidentialZone,
+			Zone::Commercial => texture::Texture::CommercialZone,
+			Zone::Industrial => texture::Texture::IndustrialZone,
+		}
+	}
+
+	fn building_texture(self) -> texture::Texture4Directional {
+		match self {
+			Zone::Residential => texture::Texture4Directional::House,
+			Zone::Commercial => texture::Texture4Directional::Shop,
+			Zone::Industrial => texture::Texture4Directional::Factory,
+		}
+	}
+
+	/// Gets the index of this zone in Zone::ALL.
+	pub fn get_index(self) -> usize {
+		match self {
+			Zone::Residential => 0,
+			Zone::Commercial => 1,
+			Zone::Industrial => 2,
+		}
+	}
+
+	pub fn get_name(self) -> &'static str {
+		match self {
+			Zone::Residential => "residential",
+			Zone::Commercial => "commercial",
+			Zone::Industrial => "industrial",
+		}
+	}
+
+	fn get_save_id(self) -> u8 {
+		match self {
+			Zone::Residential => 0,
+			Zone::Commercial => 1,
+			Zone::Industrial => 2,
+		}
+	}
+
+	fn from_save_id(id: u8) -> io::Result<Self> {
+		Ok(match id {
+			0 => Zone::Residential,
+			1 => Zone::Commercial,
+			2 => Zone::Industrial,
+			_ => return Err(save::invalid_data(format!("unknown zone id {id}"))),
+		})
+	}
+}
+
+/// A building that covers more than one tile.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Structure {
+	TownHall,
+	Warehouse,
+}
+
+impl Structure {
+	/// Gets the texture of the top left part, the other parts follow it in the atlas in the same layout as on the map.
+	pub fn texture(self, facing: direction::Direction4) -> texture::Texture {
+		match (self, facing) {
+			(Structure::TownHall, direction::Direction4::North) => texture::Texture::TownHallNorth,
+			(Structure::TownHall, direction::Direction4::East) => texture::Texture::TownHallEast,
+			(Structure::TownHall, direction::Direction4::South) => texture::Texture::TownHall,
+			(Structure::TownHall, direction::Direction4::West) => texture::Texture::TownHallWest,
+			(Structure::Warehouse, direction::Direction4::North) => texture::Texture::WarehouseNorth,
+			(Structure::Warehouse, direction::Direction4::East) => texture::Texture::WarehouseEast,
+			(Structure::Warehouse, direction::Direction4::South) => texture::Texture::Warehouse,
+			(Structure::Warehouse, direction::Direction4::West) => texture::Texture::WarehouseWest,
+		}
+	}
+
+	/// Gets the width and height of the footprint in tiles, which swap when it faces east or west.
+	pub fn get_size(self, facing: direction::Direction4) -> [u8; 2] {
+		let [width, height] = match self {
+			Structure::TownHall => [2, 2],
+			Structure::Warehouse => [3, 2],
+		};
+		match facing.get_axis() {
+			direction::Direction2::NorthSouth => [width, height],
+			direction::Direction2::EastWest => [height, width],
+		}
+	}
+
+	/// Gets the position and part of every tile covered when the top left is at origin, None if any of them would be outside a world of the given size.
+	pub fn get_footprint(self, facing: direction::Direction4, origin: [u16; 2], world_size: [u16; 2]) -> Option<Vec<([u16; 2], [u8; 2])>> {
+		let [width, height] = self.get_size(facing);
+		if origin[0] as u32 + width as u32 > world_size[0] as u32 || origin[1] as u32 + height as u32 > world_size[1] as u32 {
+			return None;
+		}
+		let mut out = Vec::new();
+		for y in 0..height {
+			for x in 0..width {
+				out.push(([origin[0] + x as u16, origin[1] + y as u16], [x, y]));
+			}
+		}
+		Some(out)
+	}
+
+	/// Gets the footprint of a placed structure from the position of one of its parts, None if any of it is outside the world.
+	pub fn get_placed_footprint(self, facing: direction::Direction4, pos: [u16; 2], part: [u8; 2], world_size: [u16; 2]) -> Option<Vec<([u16; 2], [u8; 2])>> {
+		let origin = [pos[0].checked_sub(part[0] as u16)?, pos[1].checked_sub(part[1] as u16)?];
+		self.get_footprint(facing, origin, world_size)
+	}
+
+	fn get_save_id(self) -> u8 {
+		match self {
+			Structure::TownHall => 0,
+			Structure::Warehouse => 1,
+		}
+	}
+
+	fn from_save_id(id: u8) -> io::Result<Self> {
+		Ok(match id {
+			0 => Structure::TownHall,
+			1 => Structure::Warehouse,
+			_ => return Err(save::invalid_data(format!("unknown structure id {id}"))),
+		})
+	}
+}
+
+fn load_facing(reader: &mut impl io::Read, version: u16) -> io::Result<direction::Direction4> {
+	if version < 9 {
+		return Ok(direction::Direction4::South);
+	}
+	match save::read_u8(reader)? {
+		id @ 0..=3 => Ok(direction::Direction4::new(id)),
+		id => Err(save::invalid_data(format!("unknown direction id {id}"))),
+	}
+}
+
+#[derive(Copy, Clone, PartialEq)]
 pub enum Cover {
 	None,
-	Tree,
-	TestBuilding,
-	Road([Road; 4]),
+	Tree(TreeStage),
+	/// Land set aside for buildings of a zone that have not been built yet, facing the side that needs a road.
+	Zone(Zone, direction::Direction4),
+	/// A building facing the side that its entrance is on.
+	Building(Zone, direction::Direction4),
+	/// Road quarters in each Direction8, the diagonal ones link to diagonal neighbors.
+	Road([Road; 8]),
+	Rubble,
+	/// A road over water or swamp that runs along an axis.
+	Bridge(direction::Direction2, Road),
+	/// One tile of a structure, part is the position of the tile within the footprint after it has been turned to face its facing.
+	Structure { structure: Structure, facing: direction::Direction4, part: [u8; 2] },
 }
 
 impl Cover {
-	pub fn render(&self, pos: [u16; 2]) -> Vec<vertex::Vertex> {
+	/// Gets the ids of the sprites to draw for this cover, from bottom to top.
+	pub fn get_texture_ids(&self) -> Vec<u8> {
 		match self {
 			Cover::None => Vec::new(),
-			Cover::Tree => texture::Texture::Tree.generate_tris(pos).to_vec(),
-			Cover::TestBuilding => texture::Texture::TestBuilding.generate_tris(pos).to_vec(),
-			Cover::Road (directions) => {
-				let mut out = Vec::new();
-				for (direction, road_quarter) in directions.iter().enumerate() {
-					if !matches!(road_quarter, Road::None) {
-						out.extend(road_quarter.texture().generate_tris(direction::Direction4::new(direction as u8), pos));
-					}
-				}
+			Cover::Tree(stage) => vec![stage.texture().get_texture_id()],
+			Cover::Zone(zone, facing) => vec![zone.zone_texture().get_texture_id(), texture::Texture4Directional::ZoneArrow.get_texture_id(*facing)],
+			Cover::Building(zone, facing) => vec![zone.building_texture().get_texture_id(*facing)],
+			Cover::Rubble => vec![texture::Texture::Rubble.get_texture_id()],
+			Cover::Structure { structure, facing, part } => vec![structure.texture(*facing).get_texture_id() + part[0] + part[1] * 16],
+			Cover::Bridge(axis, _) => {
+				let deck = match axis {
+					direction::Direction2::NorthSouth => texture::Texture::BridgeNorthSouth,
+					direction::Direction2::EastWest => texture::Texture::BridgeEastWest,
+				};
+				let mut out = vec![deck.get_texture_id()];
+				out.extend(Cover::Road(self.get_road_quarters().unwrap()).get_texture_ids());
 				out
 			},
+			Cover::Road (directions) => {
+				let mut quarters: Vec<(usize, Road)> = directions.iter().copied().enumerate().filter(|(_, road_quarter)| *road_quarter != Road::None).collect();
+				// Draw better roads last so they are on top at junctions
+				quarters.sort_by_key(|(_, road_quarter)| road_quarter.get_tier());
+				quarters.into_iter().map(|(direction, road_quarter)| road_quarter.get_quarter_texture_id(direction::Direction8::new(direction as u8))).collect()
+			},
+		}
+	}
+
+	/// Gets a name for this cover to show to the player.
+	pub fn get_name(&self) -> &'static str {
+		match self {
+			Cover::None => "Nothing",
+			Cover::Tree(_) => "Tree",
+			Cover::Zone(Zone::Residential, _) => "Residential zone",
+			Cover::Zone(Zone::Commercial, _) => "Commercial zone",
+			Cover::Zone(Zone::Industrial, _) => "Industrial zone",
+			Cover::Building(Zone::Residential, _) => "House",
+			Cover::Building(Zone::Commercial, _) => "Shop",
+			Cover::Building(Zone::Industrial, _) => "Factory",
+			Cover::Rubble => "Rubble",
+			Cover::Road(_) => "Road",
+			Cover::Structure { structure: Structure::TownHall, .. } => "Town hall",
+			Cover::Structure { structure: Structure::Warehouse, .. } => "Warehouse",
+			Cover::Bridge(..) => "Bridge",
+		}
+	}
+
+	/// Gets the color of this cover for one pixel per tile images.
+	pub fn get_map_color(&self) -> Option<[u8; 3]> {
+		match self {
+			Cover::None => None,
+			Cover::Tree(_) => Some([0x1E, 0x64, 0x1E]),
+			Cover::Zone(Zone::Residential, _) => Some([0x8C, 0xDC, 0x8C]),
+			Cover::Zone(Zone::Commercial, _) => Some([0x8C, 0xB4, 0xF0]),
+			Cover::Zone(Zone::Industrial, _) => Some([0xF0, 0xD2, 0x8C]),
+			Cover::Building(Zone::Residential, _) => Some([0xE6, 0xD2, 0x8C]),
+			Cover::Building(Zone::Commercial, _) => Some([0x50, 0x78, 0xC8]),
+			Cover::Building(Zone::Industrial, _) => Some([0x78, 0x78, 0x80]),
+			Cover::Rubble => Some([0x96, 0x8C, 0x7D]),
+			Cover::Road(_) => Some([0x64, 0x64, 0x64]),
+			Cover::Structure { structure: Structure::TownHall, .. } => Some([0xF0, 0xEB, 0xDC]),
+			Cover::Structure { structure: Structure::Warehouse, .. } => Some([0x8A, 0x5A, 0x3A]),
+			Cover::Bridge(..) => Some([0x8A, 0x5A, 0x30]),
 		}
 	}
 
 	pub fn can_go_on_ground(&self, ground: Ground) -> bool {
 		match self {
 			Self::None => true,
-			Self::Tree => ground.is_land() && ground.is_fertile(),
-			Self::TestBuilding => ground.is_land(),
+			Self::Tree(_) => ground.is_land() && ground.is_fertile(),
+			Self::Zone(..) => ground.is_land(),
+			Self::Building(..) => ground.is_land(),
 			Self::Road(_) => ground.is_land(),
+			Self::Rubble => ground.is_land(),
+			Self::Structure { .. } => ground.is_land(),
+			Self::Bridge(..) => ground.is_water(),
+		}
+	}
+
+	/// Gets the cost of building this cover.
+	pub fn get_build_cost(&self) -> i64 {
+		match self {
+			Cover::None => 0,
+			Cover::Tree(_) => 1500,
+			Cover::Zone(..) => 500,
+			Cover::Building(..) => 20000,
+			Cover::Road(road_quarters) => road_quarters.iter().map(|road_quarter| road_quarter.get_build_cost()).sum(),
+			Cover::Rubble => 0,
+			// The whole structure is paid for on its top left part
+			Cover::Structure { structure: Structure::TownHall, part: [0, 0], .. } => 50000,
+			Cover::Structure { structure: Structure::Warehouse, part: [0, 0], .. } => 30000,
+			Cover::Structure { .. } => 0,
+			// Bridges cost more than the two road quarters that they carry
+			Cover::Bridge(_, road) => road.get_build_cost() * 2 * 5,
+		}
+	}
+
+	/// Gets the cost of removing this cover.
+	pub fn get_demolish_cost(&self) -> i64 {
+		match self {
+			Cover::None => 0,
+			Cover::Tree(_) => 1000,
+			Cover::Zone(..) => 100,
+			Cover::Building(..) => 5000,
+			Cover::Road(road_quarters) => road_quarters.iter().map(|road_quarter| road_quarter.get_demolish_cost()).sum(),
+			Cover::Rubble => 2000,
+			Cover::Structure { part: [0, 0], .. } => 10000,
+			Cover::Structure { .. } => 0,
+			Cover::Bridge(_, road) => road.get_demolish_cost() * 2 * 5,
+		}
+	}
+
+	/// Gets the cost of keeping this cover each tick, buildings pay taxes so theirs is negative.
+	pub fn get_upkeep(&self) -> i64 {
+		match self {
+			Cover::Building(Zone::Residential, _) => -4,
+			Cover::Building(Zone::Commercial | Zone::Industrial, _) => -6,
+			Cover::Structure { structure: Structure::TownHall, part: [0, 0], .. } => 10,
+			Cover::Structure { structure: Structure::Warehouse, part: [0, 0], .. } => -20,
+			Cover::Bridge(_, road) => road.get_upkeep() * 2 * 2,
+			Cover::Road(road_quarters) => road_quarters.iter().map(|road_quarter| road_quarter.get_upkeep()).sum(),
+			_ => 0,
+		}
+	}
+
+	/// Gets the road quarters of covers that vehicles can drive on, None for other covers.
+	pub fn get_road_quarters(&self) -> Option<[Road; 8]> {
+		match self {
+			Cover::Road(road_quarters) => Some(*road_quarters),
+			Cover::Bridge(direction::Direction2::NorthSouth, road) => Some([*road, Road::None, Road::None, Road::None, *road, Road::None, Road::None, Road::None]),
+			Cover::Bridge(direction::Direction2::EastWest, road) => Some([Road::None, Road::None, *road, Road::None, Road::None, Road::None, *road, Road::None]),
+			_ => None,
+		}
+	}
+
+	/// Gets the side of a building that vehicles come and go from, None for covers that are not buildings.
+	pub fn get_entrance(&self) -> Option<direction::Direction4> {
+		match self {
+			Cover::Building(_, facing) => Some(*facing),
+			_ => None,
+		}
+	}
+
+	/// Turns a cover that has a facing a quarter turn clockwise.
+	pub fn rotate(&mut self) {
+		if let Cover::Zone(_, facing) | Cover::Building(_, facing) = self {
+			*facing = facing.get_clockwise();
+		}
+	}
+
+	/// Gets if fire can burn this cover.
+	pub fn is_flammable(&self) -> bool {
+		matches!(self, Cover::Tree(_) | Cover::Building(..))
+	}
+
+	fn save(&self, writer: &mut impl io::Write) -> io::Result<()> {
+		match self {
+			Cover::None => save::write_u8(writer, 0),
+			Cover::Tree(stage) => {
+				save::write_u8(writer, 1)?;
+				save::write_u8(writer, stage.get_save_id())
+			},
+			Cover::Building(zone, facing) => {
+				save::write_u8(writer, 2)?;
+				save::write_u8(writer, zone.get_save_id())?;
+				save::write_u8(writer, facing.get_int_val())
+			},
+			Cover::Road(road_quarters) => {
+				save::write_u8(writer, 3)?;
+				for road_quarter in road_quarters {
+					save::write_u8(writer, road_quarter.get_save_id())?;
+				}
+				Ok(())
+			},
+			Cover::Rubble => save::write_u8(writer, 4),
+			Cover::Zone(zone, facing) => {
+				save::write_u8(writer, 5)?;
+				save::write_u8(writer, zone.get_save_id())?;
+				save::write_u8(writer, facing.get_int_val())
+			},
+			Cover::Bridge(axis, road) => {
+				save::write_u8(writer, 7)?;
+				save::write_u8(writer, match axis {
+					direction::Direction2::NorthSouth => 0,
+					direction::Direction2::EastWest => 1,
+				})?;
+				save::write_u8(writer, road.get_save_id())
+			},
+			Cover::Structure { structure, facing, part } => {
+				save::write_u8(writer, 6)?;
+				save::write_u8(writer, structure.get_save_id())?;
+				save::write_u8(writer, facing.get_int_val())?;
+				save::write_u8(writer, part[0])?;
+				save::write_u8(writer, part[1])
+			},
 		}
 	}
+
+	fn load(reader: &mut impl io::Read, version: u16) -> io::Result<Self> {
+		Ok(match save::read_u8(reader)? {
+			0 => Cover::None,
+			// Trees did not have stages before version 3
+			1 if version < 3 => Cover::Tree(TreeStage::Mature),
+			1 => Cover::Tree(TreeStage::from_save_id(save::read_u8(reader)?)?),
+			// The only building before version 6 became a house, zones and buildings faced south before version 9
+			2 if version < 6 => Cover::Building(Zone::Residential, direction::Direction4::South),
+			2 => Cover::Building(Zone::from_save_id(save::read_u8(reader)?)?, load_facing(reader, version)?),
+			// Roads only had the four straight quarters before version 12
+			3 => {
+				let mut road_quarters = [Road::None; 8];
+				let quarter_indices: Vec<usize> = match version < 12 {
+					true => (0..8).step_by(2).collect(),
+					false => (0..8).collect(),
+				};
+				for index in quarter_indices {
+					road_quarters[index] = Road::from_save_id(save::read_u8(reader)?)?;
+				}
+				Cover::Road(road_quarters)
+			},
+			4 => Cover::Rubble,
+			5 => Cover::Zone(Zone::from_save_id(save::read_u8(reader)?)?, load_facing(reader, version)?),
+			6 => {
+				let structure = Structure::from_save_id(save::read_u8(reader)?)?;
+				// Structures all faced south before version 13
+				let facing = match version < 13 {
+					true => direction::Direction4::South,
+					false => load_facing(reader, version)?,
+				};
+				let part = [save::read_u8(reader)?, save::read_u8(reader)?];
+				let [width, height] = structure.get_size(facing);
+				if part[0] >= width || part[1] >= height {
+					return Err(save::invalid_data(format!("structure part {}, {} is outside its footprint", part[0], part[1])));
+				}
+				Cover::Structure { structure, facing, part }
+			},
+			7 => {
+				let axis = match save::read_u8(reader)? {
+					0 => direction::Direction2::NorthSouth,
+					1 => direction::Direction2::EastWest,
+					id => return Err(save::invalid_data(format!("unknown axis id {id}"))),
+				};
+				Cover::Bridge(axis, Road::from_save_id(save::read_u8(reader)?)?)
+			},
+			id => return Err(save::invalid_data(format!("unknown cover id {id}"))),
+		})
+	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Tile {
 	pub ground: Ground,
-	pub cover: Cover
+	pub cover: Cover,
+	/// Elevation of the ground, water flows from higher to lower tiles.
+	pub height: u8,
+	/// Ticks that land has been next to water or that swamp has been cut off from water.
+	pub water_timer: u16,
+	/// Ticks that the tile has been on fire for, 0 if not on fire.
+	pub fire: u8,
 }
 
 impl Tile {
 	pub fn new() -> Self {
-		Self { ground: Ground::Grass, cover: Cover::None }
+		Self { ground: Ground::Grass, cover: Cover::None, height: 0, water_timer: 0, fire: 0 }
+	}
+
+	/// Gets the ids of the sprites to draw for this tile, from bottom to top.
+	pub fn get_texture_ids(&self) -> Vec<u8> {
+		let mut ids = vec![self.ground.texture().get_texture_id()];
+		ids.extend(self.cover.get_texture_ids());
+		if self.fire > 0 {
+			ids.push(texture::Texture::Fire.get_texture_id());
+		}
+		ids
 	}
 
 	pub fn render(&self, pos: [u16; 2]) -> Vec<vertex::Vertex> {
-		let mut tris = self.ground.texture().generate_tris(pos).to_vec();
-		tris.extend(self.cover.render(pos));
-		tris
+		self.get_texture_ids().into_iter().flat_map(|id| texture::generate_tris_from_id(id, pos)).collect()
+	}
+
+	/// Gets the color of this tile for one pixel per tile images.
+	pub fn get_map_color(&self) -> [u8; 3] {
+		self.cover.get_map_color().unwrap_or(self.ground.get_map_color())
 	}
-}
\ No newline at end of file
+
+	pub fn save(&self, writer: &mut impl io::Write) -> io::Result<()> {
+		save::write_u8(writer, self.ground.get_save_id())?;
+		self.cover.save(writer)?;
+		save::write_u8(writer, self.height)?;
+		save::write_u16(writer, self.water_timer)?;
+		save::write_u8(writer, self.fire)
+	}
+
+	pub fn load(reader: &mut impl io::Read, version: u16) -> io::Result<Self> {
+		let ground = Ground::from_save_id(save::read_u8(reader)?)?;
+		let cover = Cover::load(reader, version)?;
+		let mut out = Self { ground, cover, ..Self::new() };
+		if version >= 4 {
+			out.height = save::read_u8(reader)?;
+			out.water_timer = save::read_u16(reader)?;
+		}
+		if version >= 5 {
+			out.fire = save::read_u8(reader)?;
+		}
+		Ok(out)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tiles_load_as_they_were_saved() {
+		let covers = [
+			Cover::None,
+			Cover::Tree(TreeStage::Old),
+			Cover::Building(Zone::Commercial, direction::Direction4::East),
+			Cover::Road([Road::Gravel, Road::None, Road::Paved, Road::Highway, Road::None, Road::DirtPath, Road::None, Road::Gravel]),
+			Cover::Rubble,
+			Cover::Zone(Zone::Industrial, direction::Direction4::West),
+			Cover::Structure { structure: Structure::Warehouse, facing: direction::Direction4::East, part: [1, 2] },
+			Cover::Bridge(direction::Direction2::EastWest, Road::Paved),
+		];
+		for cover in covers {
+			let tile = Tile { ground: Ground::Swamp, cover, height: 7, water_timer: 300, fire: 5 };
+			let mut bytes = Vec::new();
+			tile.save(&mut bytes).unwrap();
+			let mut reader = bytes.as_slice();
+			assert!(Tile::load(&mut reader, save::FORMAT_VERSION).unwrap() == tile);
+			assert!(reader.is_empty());
+		}
+	}
+
+	#[test]
+	fn version_1_roads_load_as_straight_quarters() {
+		// Grass, then a road with gravel to the north and a paved road to the south
+		let bytes = [0, 3, 1, 0, 3, 0];
+		let mut reader = bytes.as_slice();
+		let tile = Tile::load(&mut reader, 1).unwrap();
+		assert!(tile.cover == Cover::Road([Road::Gravel, Road::None, Road::None, Road::None, Road::Paved, Road::None, Road::None, Road::None]));
+		assert!(tile.height == 0 && tile.water_timer == 0 && tile.fire == 0);
+		assert!(reader.is_empty());
+	}
+
+	#[test]
+	fn version_8_zones_and_structures_load_facing_south() {
+		// Bricks, then a warehouse part, then the height, water timer and fire
+		let bytes = [2, 6, 1, 2, 1, 5, 0, 0, 0];
+		let mut reader = bytes.as_slice();
+		let tile = Tile::load(&mut reader, 8).unwrap();
+		assert!(tile.cover == Cover::Structure { structure: Structure::Warehouse, facing: direction::Direction4::South, part: [2, 1] });
+		assert!(tile.ground == Ground::Bricks && tile.height == 5);
+		assert!(reader.is_empty());
+
+		// Grass with an industrial zone
+		let bytes = [0, 5, 2, 0, 0, 0, 0];
+		let mut reader = bytes.as_slice();
+		assert!(Tile::load(&mut reader, 8).unwrap().cover == Cover::Zone(Zone::Industrial, direction::Direction4::South));
+		assert!(reader.is_empty());
+	}
+}
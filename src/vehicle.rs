@@ -0,0 +1,169 @@
+// Vehicles that drive along the roads between buildings
+
+use super::direction;
+use super::pathfinding;
+use super::random;
+use super::road_network;
+use super::texture;
+use super::tile;
+use super::vertex;
+use super::world;
+
+const SPAWN_SALT: u64 = 0x400;
+const DESTINATION_SALT: u64 = 0x401;
+// How far vehicles drive from the middle of the road towards its right edge, in tiles
+const LANE_OFFSET: f32 = 3. / 16.;
+
+/// Rules of the vehicle simulation.
+#[derive(Copy, Clone)]
+pub struct VehicleParameters {
+	/// Chance per tick that a vehicle leaves each building that is next to a road.
+	pub spawn_chance: f32,
+	/// Most vehicles that can be on the map at once.
+	pub max_vehicles: usize,
+}
+
+impl Default for VehicleParameters {
+	fn default() -> Self {
+		Self { spawn_chance: 0.002, max_vehicles: 256 }
+	}
+}
+
+pub struct Vehicle {
+	/// Road tile that the vehicle is leaving.
+	pub pos: [u16; 2],
+	/// Road tiles that are left to drive to, starting with the one being driven towards.
+	pub route: Vec<[u16; 2]>,
+	/// Ticks spent driving from pos towards the first tile of the route.
+	progress: u32,
+}
+
+impl Vehicle {
+	/// Gets the road tile that the vehicle is driving to.
+	pub fn get_destination(&self) -> [u16; 2] {
+		self.route[self.route.len() - 1]
+	}
+
+	/// Gets the direction that the vehicle is driving in.
+	fn get_direction(&self) -> direction::Direction8 {
+		direction::Direction8::from_neighbors(self.pos, self.route[0]).unwrap()
+	}
+
+	/// Finds a new route from a tile to the destination, returns false if there is none.
+	fn reroute_from(&mut self, tiles: &ndarray::Array2<tile::Tile>, start: [u16; 2]) -> bool {
+		let destination = self.get_destination();
+		let path = match pathfinding::find_path(tiles, start, destination, pathfinding::PathMode::RoadOnly) {
+			Some(valid) if valid.tiles.len() > 1 => valid,
+			_ => return false,
+		};
+		self.route.clear();
+		if start != self.pos {
+			self.route.push(start);
+		}
+		self.route.extend_from_slice(&path.tiles[1..]);
+		true
+	}
+
+	/// Checks the route after tiles have changed, finds a new one if a road on it is gone, returns false if the vehicle has to be removed.
+	fn check_route(&mut self, tiles: &ndarray::Array2<tile::Tile>) -> bool {
+		if get_link(tiles, self.pos, self.route[0]).is_none() {
+			// The road being driven along is gone so start again from the middle of the tile being left
+			self.progress = 0;
+			return self.reroute_from(tiles, self.pos);
+		}
+		let mut previous = self.route[0];
+		for &pos in &self.route[1..] {
+			if get_link(tiles, previous, pos).is_none() {
+				return self.reroute_from(tiles, self.route[0]);
+			}
+			previous = pos;
+		}
+		true
+	}
+
+	/// Drives for one tick, returns false once the vehicle has arrived.
+	fn drive(&mut self, tiles: &ndarray::Array2<tile::Tile>) -> bool {
+		self.progress += 1;
+		if self.progress >= get_ticks_per_tile(tiles, self.pos, self.route[0]) {
+			self.pos = self.route.remove(0);
+			self.progress = 0;
+		}
+		!self.route.is_empty()
+	}
+
+	pub fn render(&self, tiles: &ndarray::Array2<tile::Tile>, tick_fraction: f32) -> [vertex::Vertex; 6] {
+		let direction = self.get_direction();
+		let fraction = ((self.progress as f32 + tick_fraction) / get_ticks_per_tile(tiles, self.pos, self.route[0]) as f32).min(1.);
+		let [forward_x, forward_y] = direction.get_offset().map(|offset| offset as f32);
+		// Keep to the right of the road, a quarter turn clockwise from forward
+		let forward_length = (forward_x * forward_x + forward_y * forward_y).sqrt();
+		let [right_x, right_y] = [-forward_y / forward_length, forward_x / forward_length];
+		let pos = [
+			self.pos[0] as f32 + forward_x * fraction + right_x * LANE_OFFSET,
+			self.pos[1] as f32 + forward_y * fraction + right_y * LANE_OFFSET,
+		];
+		// There are only sprites for straight directions so diagonals use the one a bit anticlockwise
+		let sprite_direction = direction::Direction4::new(direction.get_int_val() / 2);
+		texture::generate_tris_from_id_at(texture::Texture4Directional::Vehicle.get_texture_id(sprite_direction), pos)
+	}
+}
+
+/// Gets the road that links two neighboring tiles on the first tile's side, None if they are not linked.
+fn get_link(tiles: &ndarray::Array2<tile::Tile>, from: [u16; 2], to: [u16; 2]) -> Option<tile::Road> {
+	let direction = direction::Direction8::from_neighbors(from, to)?;
+	road_network::get_connections(tiles, from).into_iter()
+		.find(|(connection_direction, _, _)| *connection_direction == direction)
+		.map(|(_, road, _)| road)
+}
+
+/// Gets how many ticks it takes to drive between two linked tiles.
+fn get_ticks_per_tile(tiles: &ndarray::Array2<tile::Tile>, from: [u16; 2], to: [u16; 2]) -> u32 {
+	match (get_link(tiles, from, to), direction::Direction8::from_neighbors(from, to)) {
+		(Some(road), Some(direction)) => road.get_link_movement_cost(direction),
+		_ => 1,
+	}
+}
+
+/// Gets the road tile in front of a building that vehicles can leave from or drive to.
+fn get_access_road(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], size: [u16; 2]) -> Option<[u16; 2]> {
+	let road_pos = tiles[[pos[0] as usize, pos[1] as usize]].cover.get_entrance()?.offset_pos(pos, size)?;
+	match road_network::get_connections(tiles, road_pos).is_empty() {
+		true => None,
+		false => Some(road_pos),
+	}
+}
+
+/// Checks every vehicle's route after tiles have changed and removes the ones that can no longer get anywhere.
+pub fn on_tiles_changed(world: &mut world::World) {
+	let tiles = &world.tiles;
+	world.vehicles.retain_mut(|vehicle| vehicle.check_route(tiles));
+}
+
+pub fn tick(world: &mut world::World) {
+	let parameters = world.vehicle_parameters;
+	let size = [world.get_width(), world.get_height()];
+	let tiles = &world.tiles;
+
+	// Drive
+	world.vehicles.retain_mut(|vehicle| vehicle.drive(tiles));
+
+	// Spawn vehicles at buildings that are next to a road and send them to another building
+	let access_roads: Vec<[u16; 2]> = tiles.indexed_iter()
+		.filter_map(|((x, y), _)| get_access_road(tiles, [x as u16, y as u16], size))
+		.collect();
+	for (index, &start) in access_roads.iter().enumerate() {
+		if world.vehicles.len() >= parameters.max_vehicles {
+			break;
+		}
+		if random::hash_unit(world.seed, &[world.tick_count, SPAWN_SALT, start[0] as u64, start[1] as u64, index as u64]) >= parameters.spawn_chance {
+			continue;
+		}
+		let destination_index = random::hash(world.seed, &[world.tick_count, DESTINATION_SALT, index as u64]) % access_roads.len() as u64;
+		let destination = access_roads[destination_index as usize];
+		if let Some(path) = pathfinding::find_path(tiles, start, destination, pathfinding::PathMode::RoadOnly) {
+			if path.tiles.len() > 1 {
+				world.vehicles.push(Vehicle { pos: start, route: path.tiles[1..].to_vec(), progress: 0 });
+			}
+		}
+	}
+}
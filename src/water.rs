@@ -0,0 +1,133 @@
+// Water flowing downhill, and land turning to swamp and back depending on nearby water
+
+use super::direction;
+use super::world_event;
+use super::random;
+use super::tile;
+use super::world;
+
+const FLOW_SALT: u64 = 0x200;
+
+/// Rules of the water simulation.
+#[derive(Copy, Clone)]
+pub struct WaterParameters {
+	/// Chance per tick that water flows onto each lower neighboring tile.
+	pub flow_chance: f32,
+	/// Chance per tick that water spreads onto each neighboring tile of the same height.
+	pub level_flow_chance: f32,
+	/// Ticks that land has to be next to water before it turns into swamp.
+	pub swamp_ticks: u16,
+	/// Ticks that swamp has to be away from water before it dries out into grass.
+	pub dry_ticks: u16,
+}
+
+impl Default for WaterParameters {
+	fn default() -> Self {
+		Self { flow_chance: 0.05, level_flow_chance: 0., swamp_ticks: 3000, dry_ticks: 3000 }
+	}
+}
+
+/// Changes the ground of a tile, removing the cover if it cannot go on the new ground.
+/// Flooding any part of a structure removes all of it.
+fn flood(tiles: &mut ndarray::Array2<tile::Tile>, pos: [u16; 2], events: &mut Vec<world_event::WorldEvent>, changed_positions: &mut Vec<[u16; 2]>) {
+	let size = [tiles.dim().0 as u16, tiles.dim().1 as u16];
+	let tile = &mut tiles[[pos[0] as usize, pos[1] as usize]];
+	tile.ground = tile::Ground::Water;
+	tile.water_timer = 0;
+	tile.fire = 0;
+	let cover = tile.cover;
+	if cover.can_go_on_ground(tile.ground) {
+		return;
+	}
+	events.push(world_event::WorldEvent::CoverFlooded { pos, cover });
+	tile.cover = tile::Cover::None;
+	changed_positions.push(pos);
+	if let tile::Cover::Structure { structure, facing, part } = cover {
+		for (part_pos, other_part) in structure.get_placed_footprint(facing, pos, part, size).unwrap_or_default() {
+			let part_tile = &mut tiles[[part_pos[0] as usize, part_pos[1] as usize]];
+			if part_tile.cover == (tile::Cover::Structure { structure, facing, part: other_part }) {
+				part_tile.cover = tile::Cover::None;
+				changed_positions.push(part_pos);
+			}
+		}
+	}
+}
+
+/// Gets if any tile next to a tile is water.
+fn is_next_to_water(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], size: [u16; 2]) -> bool {
+	(0..4)
+		.filter_map(|direction| direction::Direction4::new(direction).offset_pos(pos, size))
+		.any(|[x, y]| tiles[[x as usize, y as usize]].ground == tile::Ground::Water)
+}
+
+/// Advances the water by one tick, adding the positions whose cover changed to changed_positions.
+pub fn tick(world: &mut world::World, changed_positions: &mut Vec<[u16; 2]>) -> Vec<world_event::WorldEvent> {
+	let parameters = world.water_parameters;
+	let seed = world.seed;
+	let tick_count = world.tick_count;
+	let size = [world.get_width(), world.get_height()];
+	let mut events = Vec::new();
+
+	// Floods are applied after every tile has had its turn so that water only moves one tile per tick
+	let mut floods = Vec::new();
+	for (x, y) in ndarray::indices(world.tiles.dim()) {
+		let pos = [x as u16, y as u16];
+		let height = world.tiles[[x, y]].height;
+		match world.tiles[[x, y]].ground {
+			// Flow onto neighbors
+			tile::Ground::Water => for direction in (0..4).map(direction::Direction4::new) {
+				let neighbor_pos = match direction.offset_pos(pos, size) {
+					Some(valid) => valid,
+					None => continue,
+				};
+				let neighbor = &world.tiles[[neighbor_pos[0] as usize, neighbor_pos[1] as usize]];
+				if neighbor.ground == tile::Ground::Water {
+					continue;
+				}
+				let chance = match neighbor.height.cmp(&height) {
+					std::cmp::Ordering::Less => parameters.flow_chance,
+					std::cmp::Ordering::Equal => parameters.level_flow_chance,
+					std::cmp::Ordering::Greater => continue,
+				};
+				let random = random::hash_unit(seed, &[tick_count, FLOW_SALT, x as u64, y as u64, direction.get_int_val() as u64]);
+				if random < chance {
+					floods.push(neighbor_pos);
+				}
+			},
+			// Dry out when cut off from water
+			tile::Ground::Swamp => {
+				let is_next_to_water = is_next_to_water(&world.tiles, pos, size);
+				let tile = &mut world.tiles[[x, y]];
+				match is_next_to_water {
+					true => tile.water_timer = 0,
+					false => tile.water_timer = tile.water_timer.saturating_add(1),
+				}
+				if tile.water_timer >= parameters.dry_ticks && tile.cover.can_go_on_ground(tile::Ground::Grass) {
+					tile.ground = tile::Ground::Grass;
+					tile.water_timer = 0;
+				}
+			}
+			// Turn into swamp after being next to water for a long time
+			ground => if ground.is_land() {
+				let is_next_to_water = is_next_to_water(&world.tiles, pos, size);
+				let tile = &mut world.tiles[[x, y]];
+				match is_next_to_water {
+					true => tile.water_timer = tile.water_timer.saturating_add(1),
+					false => tile.water_timer = 0,
+				}
+				if tile.water_timer >= parameters.swamp_ticks && tile.cover.can_go_on_ground(tile::Ground::Swamp) {
+					tile.ground = tile::Ground::Swamp;
+					tile.water_timer = 0;
+				}
+			},
+		}
+	}
+
+	for pos in floods {
+		// Two tiles can flow onto the same neighbor
+		if world.tiles[[pos[0] as usize, pos[1] as usize]].ground != tile::Ground::Water {
+			flood(&mut world.tiles, pos, &mut events, changed_positions);
+		}
+	}
+	events
+}
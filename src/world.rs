@@ -1,17 +1,88 @@
-use ndarray;
+use std::{fs, io, path};
 
 use super::vertex;
+use super::texture;
 use super::tile;
 use super::interaction;
 use super::mouse;
+use super::save;
+use super::history;
+use super::forest;
+use super::water;
+use super::fire;
+use super::road_network;
+use super::vehicle;
+use super::zoning;
+use super::economy;
+use super::world_event;
+
+/// Largest width or height a world can have.
+pub const MAX_SIZE: u16 = 4096;
+
+/// Which edge of a world stays in place when it is resized, or the center to grow or crop evenly on all sides.
+#[derive(Copy, Clone)]
+pub enum ResizeAnchor {
+	North,
+	East,
+	South,
+	West,
+	Center,
+}
+
+impl ResizeAnchor {
+	/// Gets how far the old tiles are moved along each axis when resizing from the old to the new size.
+	fn get_offset(self, old_size: [u16; 2], new_size: [u16; 2]) -> [i32; 2] {
+		let (horizontal, vertical) = match self {
+			Self::North => (1, 0),
+			Self::East => (2, 1),
+			Self::South => (1, 2),
+			Self::West => (0, 1),
+			Self::Center => (1, 1),
+		};
+		let offset = |alignment: i32, old: u16, new: u16| (new as i32 - old as i32) * alignment / 2;
+		[offset(horizontal, old_size[0], new_size[0]), offset(vertical, old_size[1], new_size[1])]
+	}
+}
 
 pub struct World {
 	pub tiles: ndarray::Array2<tile::Tile>,
+	/// Seed for all randomness in the simulation.
+	pub seed: u64,
+	/// Amount of ticks simulated since the world was created.
+	pub tick_count: u64,
+	pub money: i64,
+	/// Interactions are free and there is no upkeep in sandbox mode.
+	pub is_sandbox: bool,
+	pub forest_parameters: forest::ForestParameters,
+	pub water_parameters: water::WaterParameters,
+	pub fire_parameters: fire::FireParameters,
+	/// Graph of the roads, kept up to date as tiles change.
+	pub road_network: road_network::RoadNetwork,
+	pub zone_parameters: zoning::ZoneParameters,
+	pub vehicle_parameters: vehicle::VehicleParameters,
+	/// Vehicles on the roads, these are not saved and spawn again after loading.
+	pub vehicles: Vec<vehicle::Vehicle>,
 }
 
 impl World {
-	pub fn new() -> Self {
-		let out = Self {tiles: ndarray::Array2::from_elem([256, 256], tile::Tile::new())};
+	/// Creates a flat world, sizes outside 1 to MAX_SIZE are clamped so callers should check sizes given by the user first.
+	pub fn new(width: u16, height: u16) -> Self {
+		let size = [width.clamp(1, MAX_SIZE) as usize, height.clamp(1, MAX_SIZE) as usize];
+		let tiles = ndarray::Array2::from_elem(size, tile::Tile::new());
+		let out = Self {
+			road_network: road_network::RoadNetwork::new(&tiles),
+			tiles,
+			seed: 0,
+			tick_count: 0,
+			money: economy::STARTING_MONEY,
+			is_sandbox: false,
+			forest_parameters: forest::ForestParameters::default(),
+			water_parameters: water::WaterParameters::default(),
+			fire_parameters: fire::FireParameters::default(),
+			zone_parameters: zoning::ZoneParameters::default(),
+			vehicle_parameters: vehicle::VehicleParameters::default(),
+			vehicles: Vec::new(),
+		};
 		//out.tiles[[0, 0]] = tile::Tile { ground: tile::Ground::Grass, cover: tile::Cover::Road([tile::Road::None, tile::Road::Gravel, tile::Road::None, tile::Road::None]) };
 		out
 	}
@@ -24,19 +95,155 @@ impl World {
 		self.tiles.shape()[1] as u16
 	}
 
-	pub fn interact(&mut self, interaction: &interaction::InteractionShape, mouse: &mouse::Mouse) {
-		interaction.interact(&mut self.tiles, mouse);
+	/// Grows or crops the world to a new size, keeping the anchored edge or center in place and filling new space with fresh tiles.
+	pub fn resize(&mut self, width: u16, height: u16, anchor: ResizeAnchor) {
+		let new_size = [width.clamp(1, MAX_SIZE), height.clamp(1, MAX_SIZE)];
+		let old_size = [self.get_width(), self.get_height()];
+		let offset = anchor.get_offset(old_size, new_size);
+		let mut tiles = ndarray::Array2::from_elem([new_size[0] as usize, new_size[1] as usize], tile::Tile::new());
+		for y in 0..new_size[1] as i32 {
+			for x in 0..new_size[0] as i32 {
+				let old_x = x - offset[0];
+				let old_y = y - offset[1];
+				if old_x >= 0 && old_y >= 0 && old_x < old_size[0] as i32 && old_y < old_size[1] as i32 {
+					tiles[[x as usize, y as usize]] = self.tiles[[old_x as usize, old_y as usize]].clone();
+				}
+			}
+		}
+		// Remove what is left of structures that were cut by the new edges
+		for ((x, y), tile) in tiles.indexed_iter_mut() {
+			if let tile::Cover::Structure { structure, facing, part } = tile.cover {
+				if structure.get_placed_footprint(facing, [x as u16, y as u16], part, new_size).is_none() {
+					tile.cover = tile::Cover::None;
+				}
+			}
+		}
+		self.tiles = tiles;
+		self.road_network = road_network::RoadNetwork::new(&self.tiles);
+		self.vehicles.clear();
+	}
+
+	pub fn save(&self, path: &path::Path) -> io::Result<()> {
+		let mut writer = io::BufWriter::new(fs::File::create(path)?);
+		save::write_header(&mut writer)?;
+		save::write_u16(&mut writer, self.get_width())?;
+		save::write_u16(&mut writer, self.get_height())?;
+		save::write_u64(&mut writer, self.seed)?;
+		save::write_u64(&mut writer, self.tick_count)?;
+		save::write_u64(&mut writer, self.money as u64)?;
+		save::write_u8(&mut writer, self.is_sandbox as u8)?;
+		for column in self.tiles.axis_iter(ndarray::Axis(1)) {
+			for tile in column.iter() {
+				tile.save(&mut writer)?;
+			}
+		}
+		io::Write::flush(&mut writer)
+	}
+
+	pub fn load(path: &path::Path) -> io::Result<Self> {
+		let mut reader = io::BufReader::new(fs::File::open(path)?);
+		let version = save::read_header(&mut reader)?;
+		let width = save::read_u16(&mut reader)?;
+		let height = save::read_u16(&mut reader)?;
+		if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+			return Err(save::invalid_data(format!("invalid world size {width}x{height}")));
+		}
+		let mut out = Self::new(width, height);
+		if version >= 2 {
+			out.seed = save::read_u64(&mut reader)?;
+			out.tick_count = save::read_u64(&mut reader)?;
+		}
+		if version >= 7 {
+			out.money = save::read_u64(&mut reader)? as i64;
+			out.is_sandbox = save::read_u8(&mut reader)? != 0;
+		}
+		for y in 0..height as usize {
+			for x in 0..width as usize {
+				out.tiles[[x, y]] = tile::Tile::load(&mut reader, version)?;
+			}
+		}
+		out.road_network = road_network::RoadNetwork::new(&out.tiles);
+		Ok(out)
+	}
+
+	/// Updates what depends on the tiles after some of them have been changed.
+	pub fn on_tiles_changed(&mut self, positions: &[[u16; 2]]) {
+		self.road_network.update(&self.tiles, positions);
+		vehicle::on_tiles_changed(self);
+	}
+
+	/// Applies an interaction and pays for it, returns the tiles it changed so they can be undone.
+	/// Nothing is changed if the whole interaction cannot be afforded.
+	pub fn interact(&mut self, interaction: &interaction::InteractionShape, mouse: &mouse::Mouse) -> Result<Option<history::Action>, economy::CannotAfford> {
+		let tiles_before = interaction.interact(&mut self.tiles, mouse);
+		let cost = economy::get_cost(&tiles_before, &self.tiles);
+		let mut action = match history::Action::from_difference(tiles_before.clone(), &self.tiles) {
+			Some(valid) => valid,
+			None => return Ok(None),
+		};
+		if !self.is_sandbox {
+			if cost > self.money {
+				self.restore(tiles_before);
+				return Err(economy::CannotAfford { cost, money: self.money });
+			}
+			self.money -= cost;
+			action.cost = cost;
+		}
+		self.on_tiles_changed(&action.get_positions());
+		Ok(Some(action))
+	}
+
+	/// Puts back the states of tiles from before an interaction.
+	fn restore(&mut self, tiles_before: Vec<([u16; 2], tile::Tile)>) {
+		for (pos, tile) in tiles_before {
+			self.tiles[[pos[0] as usize, pos[1] as usize]] = tile;
+		}
 	}
 
-	pub fn render(&self, interaction: &interaction::InteractionShape, mouse: &mouse::Mouse, is_paused: bool) -> Vec<vertex::Vertex> {
+	/// Advances the simulation by one fixed step, the result only depends on the tiles, seed and tick count.
+	pub fn tick(&mut self) -> Vec<world_event::WorldEvent> {
+		// Not every change has an event, so each part of the simulation also says where it changed a cover for the road network and vehicles
+		let mut changed_positions = Vec::new();
+		forest::tick(self, &mut changed_positions);
+		let mut events = water::tick(self, &mut changed_positions);
+		events.extend(fire::tick(self, &mut changed_positions));
+		zoning::tick(self, &mut changed_positions);
+		if !changed_positions.is_empty() {
+			self.on_tiles_changed(&changed_positions);
+		}
+		vehicle::tick(self);
+		economy::tick(self);
+		self.tick_count += 1;
+		events
+	}
+
+	/// Draws the world, tick_fraction is how far it is through the current tick so that vehicles move smoothly.
+	pub fn render(&self, interaction: &interaction::InteractionShape, mouse: &mouse::Mouse, is_gui_open: bool, tick_fraction: f32) -> Vec<vertex::Vertex> {
 		let mut data: Vec<vertex::Vertex> = Vec::new();
 		for (y, column) in self.tiles.axis_iter(ndarray::Axis(1)).enumerate() {
 			for (x, tile) in column.iter().enumerate() {
 				data.extend(tile.render([x as u16, y as u16]));
 			}
 		}
-		if !is_paused {
-			data.extend(interaction.generate_select_tris(&self.tiles, mouse));
+		for (pos, ids) in self.road_network.get_corner_texture_ids() {
+			data.extend(ids.iter().flat_map(|id| texture::generate_tris_from_id(*id, *pos)));
+		}
+		for vehicle in &self.vehicles {
+			data.extend(vehicle.render(&self.tiles, tick_fraction));
+		}
+		if !is_gui_open {
+			// Worked out once for both previews so that a road route is only searched for once
+			let tile_interactions = match interaction.has_cost_preview(mouse) {
+				true => interaction.get_tile_interactions(&self.tiles, mouse),
+				false => Vec::new(),
+			};
+			data.extend(interaction.generate_select_tris(&self.tiles, mouse, &tile_interactions));
+			if !self.is_sandbox && interaction.has_cost_preview(mouse) {
+				let cost = interaction::get_cost(&self.tiles, &tile_interactions);
+				if cost > 0 {
+					data.extend(economy::render_money(cost, [mouse.pos[0] as f32 + 1., mouse.pos[1] as f32 - 0.5], cost <= self.money));
+				}
+			}
 		}
 		data
 	}
@@ -0,0 +1,20 @@
+use std::fmt;
+
+use super::tile;
+
+/// Something that happened during a simulation tick that the player should know about.
+pub enum WorldEvent {
+	/// Water spread onto a tile and removed a cover that cannot be on water.
+	CoverFlooded { pos: [u16; 2], cover: tile::Cover },
+	/// A tree or building burnt down.
+	CoverBurnt { pos: [u16; 2], cover: tile::Cover },
+}
+
+impl fmt::Display for WorldEvent {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::CoverFlooded { pos: [x, y], cover } => write!(f, "{} at {x}, {y} was destroyed by a flood", cover.get_name()),
+			Self::CoverBurnt { pos: [x, y], cover } => write!(f, "{} at {x}, {y} burnt down", cover.get_name()),
+		}
+	}
+}
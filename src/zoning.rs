@@ -0,0 +1,87 @@
+// Buildings appearing on zoned land and being abandoned depending on demand
+
+use super::direction;
+use super::random;
+use super::tile;
+use super::world;
+
+const GROWTH_SALT: u64 = 0x500;
+const ABANDON_SALT: u64 = 0x501;
+
+/// Rules of the zoning simulation.
+#[derive(Copy, Clone)]
+pub struct ZoneParameters {
+	/// Chance per tick that a building appears on a zoned tile next to a road when demand for its zone is full.
+	pub growth_chance: f32,
+	/// Chance per tick that a building is abandoned when demand for its zone is at its lowest.
+	pub abandon_chance: f32,
+	/// Buildings of each zone that are wanted before anything has been built.
+	pub base_demand: f32,
+}
+
+impl Default for ZoneParameters {
+	fn default() -> Self {
+		Self { growth_chance: 0.005, abandon_chance: 0.0005, base_demand: 4. }
+	}
+}
+
+/// Gets how many buildings of each zone there are, in the order of tile::Zone::ALL.
+pub fn count_buildings(tiles: &ndarray::Array2<tile::Tile>) -> [u32; 3] {
+	let mut counts = [0; 3];
+	for tile in tiles.iter() {
+		if let tile::Cover::Building(zone, _) = tile.cover {
+			counts[zone.get_index()] += 1;
+		}
+	}
+	counts
+}
+
+/// Gets the demand for each zone from -1 to 1, in the order of tile::Zone::ALL.
+/// Homes are wanted for the people working in shops and factories, and shops and factories are wanted for the people living in homes.
+pub fn get_demand(parameters: &ZoneParameters, counts: [u32; 3]) -> [f32; 3] {
+	let [residential, commercial, industrial] = counts.map(|count| count as f32);
+	let wanted = [commercial + industrial, residential / 2., residential / 2.].map(|wanted| wanted + parameters.base_demand);
+	let built = [residential, commercial, industrial];
+	[0, 1, 2].map(|index| ((wanted[index] - built[index]) / wanted[index]).clamp(-1., 1.))
+}
+
+/// Gets if a tile has a road on the tile next to it in the direction it faces.
+fn has_road_access(tiles: &ndarray::Array2<tile::Tile>, pos: [u16; 2], facing: direction::Direction4, size: [u16; 2]) -> bool {
+	match facing.offset_pos(pos, size) {
+		Some([x, y]) => tiles[[x as usize, y as usize]].cover.get_road_quarters().is_some(),
+		None => false,
+	}
+}
+
+/// Advances the zones by one tick, adding the positions whose cover changed to changed_positions.
+pub fn tick(world: &mut world::World, changed_positions: &mut Vec<[u16; 2]>) {
+	let seed = world.seed;
+	let tick_count = world.tick_count;
+	let size = [world.get_width(), world.get_height()];
+	let demand = get_demand(&world.zone_parameters, count_buildings(&world.tiles));
+	let parameters = world.zone_parameters;
+
+	// Each tile only changes its own cover and roads never change here, so the tiles can be changed as they are read
+	for (x, y) in ndarray::indices(world.tiles.dim()) {
+		let pos = [x as u16, y as u16];
+		let tile = world.tiles[[x, y]].clone();
+		match tile.cover {
+			tile::Cover::Zone(zone, facing) => {
+				let chance = parameters.growth_chance * demand[zone.get_index()];
+				if tile.fire == 0 && tile::Cover::Building(zone, facing).can_go_on_ground(tile.ground) && has_road_access(&world.tiles, pos, facing, size) &&
+					random::hash_unit(seed, &[tick_count, GROWTH_SALT, x as u64, y as u64]) < chance {
+					world.tiles[[x, y]].cover = tile::Cover::Building(zone, facing);
+					changed_positions.push(pos);
+				}
+			}
+			tile::Cover::Building(zone, facing) => {
+				let chance = parameters.abandon_chance * -demand[zone.get_index()];
+				if tile.fire == 0 && random::hash_unit(seed, &[tick_count, ABANDON_SALT, x as u64, y as u64]) < chance {
+					world.tiles[[x, y]].cover = tile::Cover::Zone(zone, facing);
+					changed_positions.push(pos);
+				}
+			}
+			_ => {}
+		}
+	}
+}